@@ -4,8 +4,8 @@ import { WalletMultiButton } from '@solana/wallet-adapter-react-ui';
 import { PublicKey } from '@solana/web3.js';
 import { RoundStatus, TicketPosition } from './types';
 import { useLottery } from './hooks/useLottery';
-import { useLotteryTransactions } from './hooks/useLotteryTransactions';
-import { getRoundPda, getTicketPositionPda } from './utils/pdas';
+import { useLotteryTransactions, BuyTicketsResult } from './hooks/useLotteryTransactions';
+import { getRoundPda } from './utils/pdas';
 
 const LotteryPage: React.FC = () => {
     const { publicKey, connected } = useWallet();
@@ -53,7 +53,7 @@ const LotteryPage: React.FC = () => {
         findActiveRound,
     } = useLottery();
 
-    const { buyTickets, claimPrize, finalizeRound } = useLotteryTransactions(provider);
+    const { buyTickets, claimPrize, requestDraw, settleDraw } = useLotteryTransactions(provider);
 
     // canBuy calculation - FIXED: Allow buying when round doesn't exist (auto-creates on first buy)
     // Enabled when: wallet.connected && not processing && (no round OR round.status === Active)
@@ -109,42 +109,42 @@ const LotteryPage: React.FC = () => {
         }
     }, [countdownData]);
 
-    // AUTO-FINALIZE: Automatically call finalizeRound when countdown reaches 0 and round is Active
-    // Guarded with ref to prevent spam calls - call exactly ONCE per expired round
+    // AUTO-FINALIZE: requestDraw when countdown reaches 0 and round is Active, then settleDraw while Drawing
+    // Guarded with ref to prevent spam calls - one call in flight at a time
     useEffect(() => {
         const autoFinalize = async () => {
-            // Conditions: round exists, is Active, countdown reached 0, not already processing, not already finalizing
+            // Conditions: round exists, is expired Active or Drawing, not already processing, not already finalizing
+            const needsRequest = round && round.status === RoundStatus.Active && countdownData.remaining === 0;
+            const needsSettle = round && round.status === RoundStatus.Drawing;
             if (
-                round &&
-                round.status === RoundStatus.Active &&
-                countdownData.remaining === 0 &&
+                (needsRequest || needsSettle) &&
                 !isProcessing &&
                 !isFinalizingRef.current &&
                 roundNumber
             ) {
-                // Prevent multiple simultaneous calls
-                if (isFinalizingRef.current) {
-                    return;
-                }
-                
                 isFinalizingRef.current = true;
-                console.log('🔄 [AUTO-FINALIZE] Countdown reached 0, round is Active - finalizing...');
                 
                 try {
-                    await finalizeRound(roundNumber);
-                    console.log('✅ [AUTO-FINALIZE] Round finalized successfully');
+                    if (needsRequest) {
+                        console.log('🔄 [AUTO-FINALIZE] Countdown reached 0, round is Active - requesting draw...');
+                        await requestDraw(roundNumber);
+                        console.log('✅ [AUTO-FINALIZE] Draw requested, waiting for the target slot');
+                    } else {
+                        await settleDraw(roundNumber);
+                        console.log('✅ [AUTO-FINALIZE] Round settled successfully');
+                    }
                     
-                    // Refetch round state after finalization
+                    // Refetch round state after the transition
                     await refresh();
                     
                     // Note: If round was extended (still Active with 0 tickets),
                     // the countdown will automatically update from the new endTimestamp
                     // via the countdownData useMemo hook that depends on round.endTimestamp
                 } catch (error: any) {
-                    // Idempotent check - if round already finalized, that's OK
-                    if (error.message?.includes('already finalized') || 
-                        error.code === 'RoundNotActive' ||
-                        error.message?.includes('RoundNotActive')) {
+                    // Target slot not reached yet - retried on the next poll
+                    if (error.message?.includes('DrawTargetSlotNotReached')) {
+                        console.log('ℹ️ [AUTO-FINALIZE] Target slot not reached yet');
+                    } else if (error.message?.includes('RoundNotActive')) {
                         console.log('ℹ️ [AUTO-FINALIZE] Round already finalized (idempotent)');
                         // Refetch anyway to get updated state
                         await refresh();
@@ -162,7 +162,7 @@ const LotteryPage: React.FC = () => {
         const interval = setInterval(autoFinalize, 2000); // Check every 2 seconds
         
         return () => clearInterval(interval);
-    }, [round, countdownData.remaining, isProcessing, roundNumber, finalizeRound, refresh]);
+    }, [round, countdownData.remaining, isProcessing, roundNumber, requestDraw, settleDraw, refresh]);
 
     // Log round state when fetched
     useEffect(() => {
@@ -182,6 +182,30 @@ const LotteryPage: React.FC = () => {
         return `${h.toString().padStart(2, '0')}:${m.toString().padStart(2, '0')}:${s.toString().padStart(2, '0')}`;
     };
 
+    // Track a purchased TicketPosition - the buy lands in requestedRound + 1 when it rolled the round over
+    const recordTicketPosition = async (requestedRound: number, ticketCount: number, result: BuyTicketsResult) => {
+        try {
+            const ticketPosAccount = await provider?.account.ticketPosition.fetch(result.ticketPosition);
+            if (ticketPosAccount) {
+                const [roundPda] = getRoundPda(requestedRound);
+                setOwnedTicketPositions(prev => [...prev, {
+                    pda: result.ticketPosition,
+                    round: ticketPosAccount.round.equals(roundPda) ? requestedRound : requestedRound + 1,
+                    startIndex: ticketPosAccount.startIndex.toNumber(),
+                    count: ticketPosAccount.count,
+                }]);
+            }
+        } catch (err) {
+            // Fallback: use expected values if fetch fails
+            setOwnedTicketPositions(prev => [...prev, {
+                pda: result.ticketPosition,
+                round: requestedRound,
+                startIndex: round?.totalTickets?.toNumber() || 0,
+                count: ticketCount,
+            }]);
+        }
+    };
+
     // Buy tickets handler - handles RoundExpired error with automatic retry (once)
     // FIXED: Allow buying even when round doesn't exist (auto-creates on first buy)
     // Updated: Accepts ticketCount parameter for multi-ticket purchases
@@ -202,43 +226,12 @@ const LotteryPage: React.FC = () => {
         const currentRoundNumber = targetRoundNumber;
         
         try {
-            // Get current total_tickets from round (for PDA derivation and client-side tracking)
-            // If round doesn't exist, total_tickets will be 0 (round auto-creates with total_tickets = 0)
-            let currentTotalTickets = 0;
-            if (round) {
-                currentTotalTickets = round.totalTickets?.toNumber() || 0;
-            }
-            // If round doesn't exist, total_tickets = 0 (will be used for first TicketPosition)
-            
-            const [roundPda] = getRoundPda(currentRoundNumber);
-            
-            await buyTickets(currentRoundNumber, ticketCount);
+            const result = await buyTickets(currentRoundNumber, ticketCount);
             
             // Store TicketPosition PDA for this purchase (client-side tracking)
             // SCALABLE MODEL: O(1) - Store PDA immediately after successful purchase
             // Note: For multi-ticket purchases, only ONE TicketPosition PDA is created (with count = ticketCount)
-            const [ticketPositionPda] = getTicketPositionPda(roundPda, publicKey, currentTotalTickets);
-            
-            // Fetch the actual TicketPosition account to get accurate data
-            try {
-                const ticketPosAccount = await provider?.account.ticketPosition.fetch(ticketPositionPda);
-                if (ticketPosAccount) {
-                    setOwnedTicketPositions(prev => [...prev, {
-                        pda: ticketPositionPda,
-                        round: currentRoundNumber,
-                        startIndex: ticketPosAccount.startIndex.toNumber(),
-                        count: ticketPosAccount.count,
-                    }]);
-                }
-            } catch (err) {
-                // Fallback: use expected values if fetch fails
-                setOwnedTicketPositions(prev => [...prev, {
-                    pda: ticketPositionPda,
-                    round: currentRoundNumber,
-                    startIndex: currentTotalTickets,
-                    count: ticketCount,
-                }]);
-            }
+            await recordTicketPosition(currentRoundNumber, ticketCount, result);
             
             // Success - refetch round state
             await refresh();
@@ -264,39 +257,10 @@ const LotteryPage: React.FC = () => {
                     const nextRoundNumber = currentRoundNumber + 1;
                     console.log(`[BUY] Retrying buy with round ${nextRoundNumber} (single retry)...`);
                     
-                    // Fetch next round to get total_tickets for TicketPosition PDA
-                    const [nextRoundPda] = getRoundPda(nextRoundNumber);
-                    let nextTotalTickets = 0;
-                    try {
-                        const nextRoundAccount = await provider?.account.round.fetch(nextRoundPda);
-                        nextTotalTickets = nextRoundAccount?.totalTickets?.toNumber() || 0;
-                    } catch {
-                        // Next round doesn't exist yet - will be auto-created
-                    }
-                    
-                    await buyTickets(nextRoundNumber, ticketCount);
+                    const retryResult = await buyTickets(nextRoundNumber, ticketCount);
                     
                     // Store TicketPosition PDA for retry purchase
-                    const [retryTicketPosPda] = getTicketPositionPda(nextRoundPda, publicKey, nextTotalTickets);
-                    try {
-                        const retryTicketPos = await provider?.account.ticketPosition.fetch(retryTicketPosPda);
-                        if (retryTicketPos) {
-                            setOwnedTicketPositions(prev => [...prev, {
-                                pda: retryTicketPosPda,
-                                round: nextRoundNumber,
-                                startIndex: retryTicketPos.startIndex.toNumber(),
-                                count: retryTicketPos.count,
-                            }]);
-                        }
-                    } catch {
-                        // Fallback: use expected values with correct ticketCount
-                        setOwnedTicketPositions(prev => [...prev, {
-                            pda: retryTicketPosPda,
-                            round: nextRoundNumber,
-                            startIndex: nextTotalTickets,
-                            count: ticketCount,
-                        }]);
-                    }
+                    await recordTicketPosition(nextRoundNumber, ticketCount, retryResult);
                     
                     // Success after retry - refetch again
                     await refresh();
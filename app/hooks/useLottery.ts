@@ -17,12 +17,16 @@ function getRoundStatusFromAccount(status: any): RoundStatus {
     if (status?.active !== undefined) return RoundStatus.Active;
     if (status?.ended !== undefined) return RoundStatus.Ended;
     if (status?.claimed !== undefined) return RoundStatus.Claimed;
+    if (status?.cancelled !== undefined) return RoundStatus.Cancelled;
+    if (status?.drawing !== undefined) return RoundStatus.Drawing;
     // Fallback for numeric values
     if (typeof status === 'number') {
         switch (status) {
             case 0: return RoundStatus.Active;
             case 1: return RoundStatus.Ended;
             case 2: return RoundStatus.Claimed;
+            case 3: return RoundStatus.Cancelled;
+            case 4: return RoundStatus.Drawing;
         }
     }
     return RoundStatus.Active;
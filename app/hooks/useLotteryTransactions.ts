@@ -1,16 +1,26 @@
 import { useCallback } from 'react';
 import { useWallet } from '@solana/wallet-adapter-react';
-import { PublicKey } from '@solana/web3.js';
+import { PublicKey, SYSVAR_SLOT_HASHES_PUBKEY } from '@solana/web3.js';
 import * as anchor from '@coral-xyz/anchor';
 import { Program } from '@coral-xyz/anchor';
-import { ADMIN_WALLET } from '../constants';
 import { 
     getRoundPda, 
     getVaultPda,
-    getTicketPositionPda
+    getTicketPositionPda,
+    getConfigPda,
+    getStatePda,
+    getUserProfilePda
 } from '../utils/pdas';
 import { SystemProgram } from '@solana/web3.js';
 
+/**
+ * Result of a ticket purchase
+ */
+export interface BuyTicketsResult {
+    signature: string;
+    ticketPosition: PublicKey; // TicketPosition PDA created for this purchase
+}
+
 /**
  * Custom hook for lottery transactions
  * READ-ONLY for round lifecycle - all lifecycle is handled on-chain
@@ -23,59 +33,90 @@ export function useLotteryTransactions(program: Program<any> | null) {
      * Automatically handles round creation, ending, and next round creation on-chain
      * If round expired, will return RoundExpired error - frontend should retry with round_number + 1
      * 
-     * NOTE: roundNumber is passed via PDA derivation (seeds), NOT as a method argument.
-     * The IDL signature is: buyTickets(ticketCount: u8)
+     * IDL signature: buyTickets(roundNumber: u64, ticketCount: u8, clientEntropy: [u8; 32])
+     * remainingAccounts: the purchased round's fee recipient wallets (mut), in snapshot order
      */
     const buyTickets = useCallback(async (
         roundNumber: number,
         ticketCount: number
-    ): Promise<string> => {
+    ): Promise<BuyTicketsResult> => {
         if (!program || !publicKey) {
             throw new Error('Wallet not connected or program not initialized');
         }
 
         // Derive PDAs using roundNumber
+        const [configPda] = getConfigPda();
+        const [statePda] = getStatePda();
         const [roundPda] = getRoundPda(roundNumber);
         const [vaultPda] = getVaultPda(roundNumber);
+        const [nextRoundPda] = getRoundPda(roundNumber + 1);
+        const [nextVaultPda] = getVaultPda(roundNumber + 1);
+        const [userProfilePda] = getUserProfilePda(publicKey);
         
-        // Fetch Round to get current total_tickets (needed for TicketPosition PDA derivation)
-        let totalTickets = 0;
+        const config = await program.account.lotteryConfig.fetch(configPda) as any;
+        
+        // Fee split: an open round keeps the split snapshotted when it opened, a new round takes the live config
+        let feeRecipients: { wallet: PublicKey }[] = config.feeRecipients.slice(0, config.feeRecipientCount);
         try {
             const roundAccount = await program.account.round.fetch(roundPda) as any;
-            totalTickets = roundAccount.totalTickets?.toNumber() || 0;
+            if (roundAccount.status?.active !== undefined && roundAccount.feeRecipientCount > 0) {
+                feeRecipients = roundAccount.feeRecipients.slice(0, roundAccount.feeRecipientCount);
+            }
         } catch (err) {
-            // Round doesn't exist yet - will be created by instruction with total_tickets = 0
+            // Round doesn't exist yet - will be created by instruction from the live config
             console.debug('Round not found, will be auto-created');
         }
         
-        // Derive TicketPosition PDA using current total_tickets as nonce
-        const [ticketPositionPda] = getTicketPositionPda(roundPda, publicKey, totalTickets);
+        // TicketPosition nonce is the buyer's purchase_count (0 until the profile exists)
+        let purchaseCount = 0;
+        let referrerProfile: PublicKey | null = null;
+        try {
+            const userProfile = await program.account.userProfile.fetch(userProfilePda) as any;
+            purchaseCount = userProfile.purchaseCount.toNumber();
+            if (!userProfile.referrer.equals(PublicKey.default)) {
+                [referrerProfile] = getUserProfilePda(userProfile.referrer);
+            }
+        } catch (err) {
+            console.debug('User profile not found, will be auto-created');
+        }
+        const [ticketPositionPda] = getTicketPositionPda(publicKey, purchaseCount);
+        
+        // Buyer contribution to the round's entropy accumulator
+        const clientEntropy = Array.from(crypto.getRandomValues(new Uint8Array(32)));
 
         console.log("Buying tickets for round:", roundNumber);
         console.log("PDAs derived:", {
             currentRound: roundPda.toBase58(),
             currentVault: vaultPda.toBase58(),
             ticketPosition: ticketPositionPda.toBase58(),
-            nonce: totalTickets,
+            nonce: purchaseCount,
         });
 
         try {
-            // Call buyTickets(roundNumber, ticketCount) - matching IDL signature
-            // roundNumber is also passed as instruction argument (in addition to PDA seeds)
             const signature = await program.methods
-                .buyTickets(new anchor.BN(roundNumber), ticketCount)
+                .buyTickets(new anchor.BN(roundNumber), ticketCount, clientEntropy)
                 .accounts({
                     buyer: publicKey,
+                    config: configPda,
+                    state: statePda,
                     currentRound: roundPda,
+                    userProfile: userProfilePda,
+                    referrerProfile,
                     ticketPosition: ticketPositionPda, // Verified in instruction
                     currentVault: vaultPda,
-                    adminWallet: ADMIN_WALLET,
+                    nextRound: nextRoundPda,
+                    nextVault: nextVaultPda,
                     systemProgram: SystemProgram.programId,
                 })
+                .remainingAccounts(feeRecipients.map((recipient) => ({
+                    pubkey: recipient.wallet,
+                    isWritable: true,
+                    isSigner: false,
+                })))
                 .rpc();
 
             console.log("Buy tickets successful:", signature);
-            return signature;
+            return { signature, ticketPosition: ticketPositionPda };
         } catch (error: any) {
             console.error('Buy tickets error:', error);
             if (error.logs && Array.isArray(error.logs)) {
@@ -111,11 +152,10 @@ export function useLotteryTransactions(program: Program<any> | null) {
         }
 
         try {
+            const [configPda] = getConfigPda();
             const [roundPda] = getRoundPda(roundNumber);
             const [vaultPda] = getVaultPda(roundNumber);
-            const nextRoundNum = roundNumber + 1;
-            const [nextRoundPda] = getRoundPda(nextRoundNum);
-            const [nextVaultPda] = getVaultPda(nextRoundNum);
+            const [userProfilePda] = getUserProfilePda(publicKey);
 
             console.log("Claiming prize for round:", roundNumber);
             console.log("Using TicketPosition:", ticketPositionPubkey.toBase58());
@@ -123,11 +163,11 @@ export function useLotteryTransactions(program: Program<any> | null) {
             const signature = await program.methods
                 .claimPrize()
                 .accounts({
+                    config: configPda,
                     round: roundPda,
                     ticketPosition: ticketPositionPubkey, // Proof of ownership
                     roundVault: vaultPda,
-                    nextRound: nextRoundPda,
-                    nextVault: nextVaultPda,
+                    userProfile: userProfilePda, // Records the win
                     winner: publicKey,
                     systemProgram: SystemProgram.programId,
                 })
@@ -142,11 +182,12 @@ export function useLotteryTransactions(program: Program<any> | null) {
     }, [program, publicKey]);
 
     /**
-     * Finalize round - permissionless round finalization
-     * Anyone can call this to finalize an expired active round
-     * Idempotent - safe to call multiple times
+     * Request draw - permissionless, closes an expired Active round for drawing
+     * Slot hash mode: binds the draw to a future slot (status -> Drawing), settle with settleDraw
+     * Also re-requests a slot whose hash expired and cancels rounds whose draw can no longer happen
+     * Oracle mode (config.oracleProgram set) needs the oracle accounts and is not handled here
      */
-    const finalizeRound = useCallback(async (
+    const requestDraw = useCallback(async (
         roundNumber: number
     ): Promise<string> => {
         if (!program) {
@@ -154,37 +195,76 @@ export function useLotteryTransactions(program: Program<any> | null) {
         }
 
         try {
+            const [configPda] = getConfigPda();
             const [roundPda] = getRoundPda(roundNumber);
 
-            console.log("Finalizing round:", roundNumber);
+            console.log("Requesting draw for round:", roundNumber);
 
             const signature = await program.methods
-                .finalizeRound()
+                .requestDraw()
                 .accounts({
+                    config: configPda,
                     round: roundPda,
+                    oracleProgram: null,
+                    oracleRequest: null,
+                    payer: null,
                     systemProgram: SystemProgram.programId,
                 })
                 .rpc();
 
-            console.log("Finalize round successful:", signature);
+            console.log("Request draw successful:", signature);
             return signature;
         } catch (error: any) {
-            console.error('Finalize round error:', error);
+            console.error('Request draw error:', error);
             
-            // Check if round is already finalized (idempotent check)
-            if (error.code === 6003 || error.message?.includes('RoundNotActive') || error.message?.includes('Round is not active')) {
-                // Round already finalized - this is OK (idempotent)
-                console.log('Round already finalized (idempotent call)');
+            // Round already closed for drawing (idempotent check)
+            if (error.message?.includes('RoundNotActive') || error.message?.includes('Round is not active')) {
+                console.log('Draw already requested (idempotent call)');
                 return '';
             }
             
-            throw new Error(error.message || 'Failed to finalize round');
+            throw new Error(error.message || 'Failed to request draw');
+        }
+    }, [program]);
+
+    /**
+     * Settle draw - permissionless, picks the winner from the target slot's hash
+     * Fails with DrawTargetSlotNotReached until the slot fixed by requestDraw has passed
+     */
+    const settleDraw = useCallback(async (
+        roundNumber: number
+    ): Promise<string> => {
+        if (!program) {
+            throw new Error('Program not initialized');
+        }
+
+        try {
+            const [configPda] = getConfigPda();
+            const [roundPda] = getRoundPda(roundNumber);
+
+            console.log("Settling draw for round:", roundNumber);
+
+            const signature = await program.methods
+                .settleDraw()
+                .accounts({
+                    config: configPda,
+                    round: roundPda,
+                    slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+                })
+                .rpc();
+
+            console.log("Settle draw successful:", signature);
+            return signature;
+        } catch (error: any) {
+            console.error('Settle draw error:', error);
+            throw new Error(error.message || 'Failed to settle draw');
         }
     }, [program]);
 
     return {
         buyTickets,
         claimPrize,
-        finalizeRound,
+        requestDraw,
+        settleDraw,
     };
 }
@@ -18,7 +18,7 @@ export interface Round {
 /**
  * TicketPosition account structure - proof of ticket ownership
  * Fixed-size account, one per purchase
- * PDA seeds: ["ticket", round.key(), buyer.key(), round.total_tickets.to_le_bytes()]
+ * PDA seeds: ["ticket", buyer.key(), user_profile.purchase_count.to_le_bytes()]
  */
 export interface TicketPosition {
     round: PublicKey; // Reference to the Round PDA
@@ -44,4 +44,6 @@ export enum RoundStatus {
     Active = 0,   // Round is active and accepting tickets
     Ended = 1,    // Round has ended, winner selected
     Claimed = 2,  // Prize has been claimed, round closed
+    Cancelled = 3, // Draw never completed, tickets refundable via claimRefund
+    Drawing = 4,  // Sales closed, waiting for settleDraw / oracle / reveal
 }
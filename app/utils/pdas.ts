@@ -19,30 +19,44 @@ export function getRoundPda(roundNumber: anchor.BN | number): [PublicKey, number
 
 /**
  * Derive the TicketPosition PDA for a purchase
- * Seeds: ["ticket", round_pubkey, buyer_pubkey, start_index.to_le_bytes()]
- * Note: start_index is round.total_tickets at time of purchase (0-based index)
- * Matches on-chain: ["ticket", round.key(), buyer.key(), round.total_tickets.to_le_bytes()]
+ * Seeds: ["ticket", buyer_pubkey, purchase_count.to_le_bytes()]
+ * Note: purchase_count is the buyer's UserProfile.purchase_count before the purchase (0 for a new profile)
+ * Matches on-chain: ["ticket", buyer.key(), user_profile.purchase_count.to_le_bytes()]
  */
 export function getTicketPositionPda(
-    roundPubkey: PublicKey,
     buyerPubkey: PublicKey,
-    startIndex: anchor.BN | number
+    purchaseCount: anchor.BN | number
 ): [PublicKey, number] {
-    const startIndexValue = typeof startIndex === 'number' ? startIndex : startIndex.toNumber();
-    const startIndexBuffer = Buffer.allocUnsafe(8);
-    startIndexBuffer.writeBigUInt64LE(BigInt(startIndexValue), 0);
+    const purchaseCountValue = typeof purchaseCount === 'number' ? purchaseCount : purchaseCount.toNumber();
+    const purchaseCountBuffer = Buffer.allocUnsafe(8);
+    purchaseCountBuffer.writeBigUInt64LE(BigInt(purchaseCountValue), 0);
     
     return PublicKey.findProgramAddressSync(
         [
             Buffer.from('ticket'),
-            roundPubkey.toBuffer(),
             buyerPubkey.toBuffer(),
-            startIndexBuffer,
+            purchaseCountBuffer,
         ],
         LOTTERY_PROGRAM_ID
     );
 }
 
+/**
+ * Derive the LotteryConfig PDA
+ * Seeds: ["config"]
+ */
+export function getConfigPda(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([Buffer.from('config')], LOTTERY_PROGRAM_ID);
+}
+
+/**
+ * Derive the LotteryState PDA (current round sequencer)
+ * Seeds: ["state"]
+ */
+export function getStatePda(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([Buffer.from('state')], LOTTERY_PROGRAM_ID);
+}
+
 /**
  * Derive the Vault PDA for a given round number
  * Seeds: ["vault", round_number.to_le_bytes()]
@@ -44,6 +44,10 @@ function convertIdlToOldFormat(idl: any): any {
                         isMut: acc.isMut !== undefined ? acc.isMut : (acc.writable !== false),
                         isSigner: acc.isSigner !== undefined ? acc.isSigner : (acc.signer === true),
                     };
+                    // Optional accounts (e.g. referrerProfile, oracleRequest) may be passed as null
+                    if (acc.isOptional || acc.optional) {
+                        newAcc.isOptional = true;
+                    }
                     return newAcc;
                 });
             }
@@ -169,7 +173,7 @@ import { getRoundPda } from './pdas';
 /**
  * Re-export PDA functions for convenience
  */
-export { getRoundPda, getVaultPda, getTicketPositionPda } from './pdas';
+export { getRoundPda, getVaultPda, getTicketPositionPda, getConfigPda, getStatePda } from './pdas';
 
 /**
  * Create an Anchor provider from wallet adapter
@@ -240,12 +244,16 @@ function getRoundStatusFromAccount(status: any): RoundStatus {
     if (status?.active !== undefined) return RoundStatus.Active;
     if (status?.ended !== undefined) return RoundStatus.Ended;
     if (status?.claimed !== undefined) return RoundStatus.Claimed;
+    if (status?.cancelled !== undefined) return RoundStatus.Cancelled;
+    if (status?.drawing !== undefined) return RoundStatus.Drawing;
     // Fallback for numeric values
     if (typeof status === 'number') {
         switch (status) {
             case 0: return RoundStatus.Active;
             case 1: return RoundStatus.Ended;
             case 2: return RoundStatus.Claimed;
+            case 3: return RoundStatus.Cancelled;
+            case 4: return RoundStatus.Drawing;
         }
     }
     return RoundStatus.Active;
@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+
+declare_id!("58PGjpFwMLikBhvT3m4TxoCYqBViL37kqRJYLcdW4bYR");
+
+/// Mock VRF oracle - local stand-in for an external randomness service
+/// Lifecycle: requester calls request_randomness (CPI) -> oracle authority calls fulfill_randomness
+/// -> requester reads the fulfilled RandomnessRequest account and settles
+/// NOT FOR PRODUCTION: the authority chooses the randomness, there is no VRF proof
+#[program]
+pub mod mock_oracle {
+    use super::*;
+
+    /// Initialize oracle - one-time creation of the OracleState singleton
+    /// The signer becomes the only key allowed to fulfil requests
+    pub fn initialize_oracle(ctx: Context<InitializeOracle>) -> Result<()> {
+        let oracle = &mut ctx.accounts.oracle;
+        oracle.authority = ctx.accounts.authority.key();
+        oracle.bump = ctx.bumps.oracle;
+
+        msg!("Mock oracle initialized. Authority: {}", oracle.authority);
+
+        Ok(())
+    }
+
+    /// Request randomness - creates a pending RandomnessRequest owned by this program
+    /// Requester is usually a PDA of the calling program, signing via invoke_signed
+    pub fn request_randomness(ctx: Context<RequestRandomness>, seed: [u8; 32]) -> Result<()> {
+        let request = &mut ctx.accounts.request;
+        request.requester = ctx.accounts.requester.key();
+        request.seed = seed;
+        request.fulfilled = false;
+        request.randomness = [0u8; 32];
+        request.bump = ctx.bumps.request;
+
+        msg!("Randomness requested by {}", request.requester);
+
+        Ok(())
+    }
+
+    /// Fulfill randomness - oracle authority writes the result (once)
+    pub fn fulfill_randomness(ctx: Context<FulfillRandomness>, randomness: [u8; 32]) -> Result<()> {
+        let request = &mut ctx.accounts.request;
+        require!(!request.fulfilled, OracleError::AlreadyFulfilled);
+
+        request.randomness = randomness;
+        request.fulfilled = true;
+
+        msg!("Randomness request {} fulfilled", request.key());
+
+        Ok(())
+    }
+}
+
+// ============ Account Structures ============
+
+/// Oracle State - holds the fulfilment authority
+/// PDA seeds: ["oracle"]
+#[account]
+pub struct OracleState {
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+impl OracleState {
+    pub const SIZE: usize = 8 +  // discriminator
+        32 + // authority: Pubkey
+        1;   // bump: u8
+}
+
+/// Randomness Request - one per request, read by the requester after fulfilment
+/// PDA seeds: ["request", requester.key().as_ref(), seed]
+#[account]
+pub struct RandomnessRequest {
+    pub requester: Pubkey,
+    pub seed: [u8; 32],
+    pub fulfilled: bool,
+    pub randomness: [u8; 32],  // Valid once fulfilled
+    pub bump: u8,
+}
+
+impl RandomnessRequest {
+    pub const SIZE: usize = 8 +  // discriminator
+        32 + // requester: Pubkey
+        32 + // seed: [u8; 32]
+        1 +  // fulfilled: bool
+        32 + // randomness: [u8; 32]
+        1;   // bump: u8
+}
+
+// ============ Instruction Contexts ============
+
+#[derive(Accounts)]
+pub struct InitializeOracle<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = OracleState::SIZE,
+        seeds = [b"oracle"],
+        bump
+    )]
+    pub oracle: Account<'info, OracleState>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(seed: [u8; 32])]
+pub struct RequestRandomness<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = RandomnessRequest::SIZE,
+        seeds = [b"request", requester.key().as_ref(), seed.as_ref()],
+        bump
+    )]
+    pub request: Account<'info, RandomnessRequest>,
+
+    /// Account the randomness is requested for (e.g. a lottery round PDA)
+    pub requester: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FulfillRandomness<'info> {
+    #[account(seeds = [b"oracle"], bump = oracle.bump, has_one = authority)]
+    pub oracle: Account<'info, OracleState>,
+
+    #[account(mut)]
+    pub request: Account<'info, RandomnessRequest>,
+
+    pub authority: Signer<'info>,
+}
+
+// ============ Error Codes ============
+
+#[error_code]
+pub enum OracleError {
+    #[msg("Randomness request has already been fulfilled.")]
+    AlreadyFulfilled,
+}
@@ -0,0 +1,216 @@
+//! Winner selection shared by every draw path (settle_draw, reveal_round_secret,
+//! consume_randomness)
+//!
+//! seed          = slot_hash_seed, oracle_seed or secret_seed - each mixes in the round's
+//!                 buyer entropy accumulator
+//! hash          = sha256(round_number LE || seed)
+//! winning_index = first u64 LE word of hash below the largest multiple of total_tickets,
+//!                 % total_tickets (rejection sampling - no modulo bias, see winning_index)
+//!
+//! The seed, slot and hash are stored in Round::draw_proof together with the seed's inputs
+//! (slot hash, oracle randomness or revealed secret, buyer entropy accumulator), so anyone can
+//! recompute both the seed and winning_index from the round account alone
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use std::convert::TryInto;
+
+/// Inputs and output of a draw, stored in the Round
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct DrawProof {
+    pub seed: [u8; 32],                 // Entropy fed to the draw (slot hash, oracle randomness or secret + slot hash, each mixed with buyer entropy)
+    pub slot: u64,                      // Slot the entropy was taken at
+    pub hash: [u8; 32],                 // sha256(round_number LE || seed)
+    pub slot_hash: [u8; 32],            // Hash of slot (slot hash and commit-reveal draws, zero for oracle draws)
+    pub randomness: [u8; 32],           // Oracle randomness or the revealed operator secret (zero for slot hash draws)
+    pub entropy_accumulator: [u8; 32],  // Buyer entropy accumulator mixed into the seed
+}
+
+impl DrawProof {
+    /// seed, slot and hash - the layout before the seed inputs were stored (Round v4-v11)
+    pub const V4_SIZE: usize = 32 + // seed: [u8; 32]
+        8 +  // slot: u64
+        32;  // hash: [u8; 32]
+
+    pub const SIZE: usize = Self::V4_SIZE +
+        32 + // slot_hash: [u8; 32]
+        32 + // randomness: [u8; 32]
+        32;  // entropy_accumulator: [u8; 32]
+
+    /// Recompute the seed from the stored inputs (the draw path follows from which inputs are set)
+    pub fn expected_seed(&self) -> [u8; 32] {
+        if self.randomness == [0u8; 32] {
+            slot_hash_seed(&self.slot_hash, &self.entropy_accumulator)
+        } else if self.slot_hash == [0u8; 32] {
+            oracle_seed(&self.randomness, &self.entropy_accumulator)
+        } else {
+            secret_seed(&self.randomness, &self.slot_hash, &self.entropy_accumulator)
+        }
+    }
+}
+
+/// Run a draw over total_tickets (> 0) and return (winning_index, proof); the caller fills in the seed inputs
+pub fn draw(round_number: u64, total_tickets: u64, seed: [u8; 32], slot: u64) -> (u64, DrawProof) {
+    let hash = hashv(&[round_number.to_le_bytes().as_ref(), seed.as_ref()]).to_bytes();
+    let proof = DrawProof { seed, slot, hash, ..DrawProof::default() };
+    (winning_index(&hash, total_tickets), proof)
+}
+
+/// Map a draw hash onto 0..total_tickets without modulo bias
+/// Each of the four u64 words is tried in order and accepted only below the largest multiple of
+/// total_tickets, so every index is hit by the same number of word values. If all four words are
+/// rejected (probability < (total_tickets / 2^64)^4) the hash is re-hashed and sampling continues
+pub fn winning_index(hash: &[u8; 32], total_tickets: u64) -> u64 {
+    // 2^64 mod total_tickets: the count of word values that would favour low indices
+    let rejected = (u64::MAX % total_tickets + 1) % total_tickets;
+    let limit = u64::MAX - rejected;
+    
+    let mut block = *hash;
+    loop {
+        for word in block.chunks_exact(8) {
+            let value = u64::from_le_bytes(word.try_into().unwrap());
+            if value <= limit {
+                return value % total_tickets;
+            }
+        }
+        block = hashv(&[block.as_ref()]).to_bytes();
+    }
+}
+
+/// Seed for slot hash draws: the slot hash mixed with the round's buyer entropy accumulator
+pub fn slot_hash_seed(slot_hash: &[u8; 32], entropy_accumulator: &[u8; 32]) -> [u8; 32] {
+    hashv(&[slot_hash.as_ref(), entropy_accumulator.as_ref()]).to_bytes()
+}
+
+/// Fold one purchase into the accumulator: sha256(accumulator || buyer || client_entropy)
+pub fn accumulate_entropy(accumulator: &[u8; 32], buyer: &Pubkey, client_entropy: &[u8; 32]) -> [u8; 32] {
+    hashv(&[accumulator.as_ref(), buyer.as_ref(), client_entropy.as_ref()]).to_bytes()
+}
+
+/// Seed for commit-reveal draws: the operator secret mixed with a slot hash and the buyer entropy accumulator
+pub fn secret_seed(secret: &[u8; 32], slot_hash: &[u8; 32], entropy_accumulator: &[u8; 32]) -> [u8; 32] {
+    hashv(&[secret.as_ref(), slot_hash.as_ref(), entropy_accumulator.as_ref()]).to_bytes()
+}
+
+/// Seed for oracle draws: the oracle randomness mixed with the buyer entropy accumulator
+pub fn oracle_seed(randomness: &[u8; 32], entropy_accumulator: &[u8; 32]) -> [u8; 32] {
+    hashv(&[randomness.as_ref(), entropy_accumulator.as_ref()]).to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MAX_TICKETS_PER_ROUND;
+
+    /// splitmix64 - fast deterministic hash source for the statistical sweep
+    fn next_hash(state: &mut u64) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        for word in hash.chunks_exact_mut(8) {
+            *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = *state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            word.copy_from_slice(&(z ^ (z >> 31)).to_le_bytes());
+        }
+        hash
+    }
+
+    /// Chi-square per ticket count, so a bias at one n cannot hide in a sum over all of them
+    #[test]
+    fn uniform_for_each_ticket_count() {
+        // Mostly not powers of two, where rejection sampling actually rejects
+        const TICKET_COUNTS: [u64; 12] = [3, 5, 6, 7, 10, 12, 37, 100, 333, 500, 999, MAX_TICKETS_PER_ROUND as u64];
+        const DRAWS_PER_TICKET: u64 = 200;
+        let mut state = 0x5EED_u64;
+        
+        for total_tickets in TICKET_COUNTS {
+            let mut counts = vec![0u64; total_tickets as usize];
+            for _ in 0..DRAWS_PER_TICKET * total_tickets {
+                counts[winning_index(&next_hash(&mut state), total_tickets) as usize] += 1;
+            }
+            
+            let expected = DRAWS_PER_TICKET as f64;
+            let chi_square = counts
+                .iter()
+                .map(|&count| (count as f64 - expected).powi(2) / expected)
+                .sum::<f64>();
+            
+            // Wilson-Hilferty: (chi2 / k)^(1/3) is ~normal with mean 1 - 2/(9k), variance 2/(9k)
+            let k = (total_tickets - 1) as f64;
+            let variance = 2.0 / (9.0 * k);
+            let z = ((chi_square / k).cbrt() - (1.0 - variance)) / variance.sqrt();
+            assert!(z < 4.5, "{} tickets: chi-square {} over {} df (z = {})", total_tickets, chi_square, k, z);
+        }
+    }
+
+    #[test]
+    fn index_in_range_for_all_ticket_counts() {
+        let mut state = 0xB0B_u64;
+        for total_tickets in 1..=MAX_TICKETS_PER_ROUND as u64 {
+            for _ in 0..20 {
+                assert!(winning_index(&next_hash(&mut state), total_tickets) < total_tickets);
+            }
+        }
+    }
+
+    /// Low indices must not be favoured: for 3 * 2^62 tickets a plain `% n` over one word
+    /// would hit the first 2^62 indices twice as often
+    #[test]
+    fn no_modulo_bias_for_large_counts() {
+        let total_tickets = 3u64 << 62;
+        let mut state = 0xB1A5_u64;
+        let draws = 100_000;
+        let low = (0..draws)
+            .filter(|_| winning_index(&next_hash(&mut state), total_tickets) < (1u64 << 62))
+            .count();
+        
+        // Unbiased: ~1/3 of draws below 2^62 (plain modulo: ~1/2)
+        let share = low as f64 / draws as f64;
+        assert!((share - 1.0 / 3.0).abs() < 0.01, "share below 2^62 = {}", share);
+    }
+
+    #[test]
+    fn rejected_word_falls_through_to_next_word() {
+        // 2^64 - 1 is divisible by 3, so u64::MAX is the single rejected value for 3 tickets
+        let mut hash = [0u8; 32];
+        hash[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        hash[8..16].copy_from_slice(&5u64.to_le_bytes());
+        assert_eq!(winning_index(&hash, 3), 2);
+    }
+
+    #[test]
+    fn every_seed_depends_on_buyer_entropy() {
+        let (input, secret) = ([1u8; 32], [2u8; 32]);
+        let (acc_a, acc_b) = ([3u8; 32], [4u8; 32]);
+        assert_ne!(slot_hash_seed(&input, &acc_a), slot_hash_seed(&input, &acc_b));
+        assert_ne!(oracle_seed(&input, &acc_a), oracle_seed(&input, &acc_b));
+        assert_ne!(secret_seed(&secret, &input, &acc_a), secret_seed(&secret, &input, &acc_b));
+    }
+
+    #[test]
+    fn seed_is_reproducible_from_proof_inputs() {
+        let (slot_hash, secret, acc) = ([5u8; 32], [6u8; 32], [7u8; 32]);
+        let proof = |slot_hash, randomness, seed| DrawProof {
+            seed,
+            slot_hash,
+            randomness,
+            entropy_accumulator: acc,
+            ..DrawProof::default()
+        };
+        
+        let slot_draw = proof(slot_hash, [0u8; 32], slot_hash_seed(&slot_hash, &acc));
+        let oracle_draw = proof([0u8; 32], secret, oracle_seed(&secret, &acc));
+        let reveal_draw = proof(slot_hash, secret, secret_seed(&secret, &slot_hash, &acc));
+        for proof in [slot_draw, oracle_draw, reveal_draw] {
+            assert_eq!(proof.expected_seed(), proof.seed);
+        }
+    }
+
+    #[test]
+    fn draw_is_reproducible_from_proof() {
+        let (index, proof) = draw(42, 7, [9u8; 32], 1234);
+        let (replayed, _) = draw(42, 7, proof.seed, proof.slot);
+        assert_eq!(index, replayed);
+        assert_eq!(index, winning_index(&proof.hash, 7));
+    }
+}
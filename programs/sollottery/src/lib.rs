@@ -1,58 +1,793 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::Discriminator;
 use std::convert::TryInto;
 
+pub mod draw;
+use draw::DrawProof;
+pub mod oracle;
+
+// Cluster selection: build with exactly one of the `localnet` / `devnet` / `mainnet` features
+#[cfg(not(any(feature = "localnet", feature = "devnet", feature = "mainnet")))]
+compile_error!("Select a cluster: enable exactly one of the `localnet`, `devnet` or `mainnet` features");
+
+#[cfg(any(
+    all(feature = "localnet", feature = "devnet"),
+    all(feature = "localnet", feature = "mainnet"),
+    all(feature = "devnet", feature = "mainnet")
+))]
+compile_error!("The `localnet`, `devnet` and `mainnet` features are mutually exclusive (use --no-default-features)");
+
+// A mainnet build must never ship with 60-second dev rounds
+#[cfg(all(feature = "mainnet", feature = "dev-mode"))]
+compile_error!("The `dev-mode` feature must not be enabled in a `mainnet` build");
+
+// All clusters currently deploy the same program keypair
+#[cfg(feature = "localnet")]
+declare_id!("EuLcEdX49Neyk7jhV4FQS9MmP7qpmN5Hw2dAKv1TtmtV");
+#[cfg(feature = "devnet")]
+declare_id!("EuLcEdX49Neyk7jhV4FQS9MmP7qpmN5Hw2dAKv1TtmtV");
+#[cfg(feature = "mainnet")]
 declare_id!("EuLcEdX49Neyk7jhV4FQS9MmP7qpmN5Hw2dAKv1TtmtV");
 
-// Constants (1 SOL = 1_000_000_000 lamports)
+// Default economics written into LotteryConfig by initialize_config (1 SOL = 1_000_000_000 lamports)
 const TICKET_PRICE_LAMPORTS: u64 = 11_000_000; // 0.011 SOL
 const VAULT_AMOUNT_LAMPORTS: u64 = 10_000_000; // 0.01 SOL per ticket to prize pool
 const ADMIN_AMOUNT_LAMPORTS: u64 = 1_000_000; // 0.001 SOL per ticket to admin
-const MAX_TICKETS_PER_ROUND: u16 = 1000;
-const MAX_TICKETS_PER_TX: u8 = 10;
-const MAX_BUYERS_PER_ROUND: usize = 1000; // Maximum buyers per round to prevent account size issues
+const MAX_TICKETS_PER_ROUND: u16 = 1000; // Hard upper bound for config.max_tickets_per_round
+const MAX_TICKETS_PER_TX: u8 = 10; // One TicketPosition range per purchase
 const ACTIVATION_FEE_LAMPORTS: u64 = 10_000_000; // 0.01 SOL for one-time activation
-const SANITY_TEST_TRANSFER_LAMPORTS: u64 = 10_000_000; // 0.01 SOL for sanity test transfer
+const MAX_MULTISIG_SIGNERS: usize = 10; // Fits the u16 approval bitmask in Proposal
+const MAX_FEE_RECIPIENTS: usize = 4; // e.g. treasury, operations, charity partner, burn/reserve
+const BPS_DENOMINATOR: u64 = 10_000;
+const REVEAL_WINDOW_SECONDS: i64 = 3_600; // Default operator reveal window after end_timestamp
+const DRAW_TARGET_SLOT_OFFSET: u64 = 10; // request_draw binds the draw to the hash of slot N + 10
+const ORACLE_FULFILMENT_WINDOW_SECONDS: i64 = 3_600; // Unfulfilled oracle draws can be cancelled after this
+const ROUND_HISTORY_LEN: usize = 32; // Closed-round results kept in RoundHistory (ring buffer)
+
+// Pause flags (config.paused bitmask) - each blocks one instruction family
+const PAUSE_BUY_TICKETS: u8 = 1 << 0;
+const PAUSE_FINALIZE: u8 = 1 << 1;
+const PAUSE_CLAIMS: u8 = 1 << 2; // claim_prize, claim_refund and claim_referral_rewards
+const PAUSE_ACTIVATION: u8 = 1 << 3;
+const PAUSE_ALL: u8 = PAUSE_BUY_TICKETS | PAUSE_FINALIZE | PAUSE_CLAIMS | PAUSE_ACTIVATION;
+
+// Admin key (initial authority and fee wallet) per cluster
+// Mainnet never falls back to the committed admin-keypair.json key: it must be supplied at build time
+#[cfg(any(feature = "localnet", feature = "devnet"))]
 const ADMIN_WALLET_PUBKEY_STR: &str = "2q79WzkjgEqPoBAWeEP2ih51q6TYp8D9DYWWMeLHK6WP";
+#[cfg(feature = "mainnet")]
+const ADMIN_WALLET_PUBKEY_STR: &str = env!(
+    "SOLLOTTERY_MAINNET_ADMIN",
+    "set SOLLOTTERY_MAINNET_ADMIN to the mainnet admin pubkey"
+);
 
-// Devnet program ID
-const DEVNET_PROGRAM_ID: &str = "EuLcEdX49Neyk7jhV4FQS9MmP7qpmN5Hw2dAKv1TtmtV";
+// Round duration per cluster
+#[cfg(feature = "localnet")]
+const CLUSTER_ROUND_DURATION_SECONDS: i64 = 60; // 1 minute on a local validator
+#[cfg(feature = "devnet")]
+const CLUSTER_ROUND_DURATION_SECONDS: i64 = 120; // 2 minutes for devnet testing
+#[cfg(feature = "mainnet")]
+const CLUSTER_ROUND_DURATION_SECONDS: i64 = 86_400; // 24 hours for mainnet
 
-// Round duration constants
-const ROUND_DURATION_DEVNET_SECONDS: i64 = 120; // 2 minutes for devnet testing
-const ROUND_DURATION_MAINNET_SECONDS: i64 = 86_400; // 24 hours for mainnet
+// Delay between queue_config_change and execute_config_change per cluster
+#[cfg(feature = "localnet")]
+const DEFAULT_TIMELOCK_DELAY_SECONDS: i64 = 0; // Instant on a local validator
+#[cfg(feature = "devnet")]
+const DEFAULT_TIMELOCK_DELAY_SECONDS: i64 = 300; // 5 minutes for devnet testing
+#[cfg(feature = "mainnet")]
+const DEFAULT_TIMELOCK_DELAY_SECONDS: i64 = 172_800; // 48 hours for mainnet
 
-// DEV_MODE: When true, rounds last 60 seconds for rapid testing
-const DEV_MODE: bool = true;
+// dev-mode: rounds last 60 seconds for rapid testing (rejected on mainnet above)
+const DEFAULT_ROUND_DURATION_SECONDS: i64 = if cfg!(feature = "dev-mode") {
+    60
+} else {
+    CLUSTER_ROUND_DURATION_SECONDS
+};
 
-/// Get round duration based on DEV_MODE flag and cluster
-fn get_round_duration() -> i64 {
-    if DEV_MODE {
-        return 60;
+fn get_admin_wallet_pubkey() -> Pubkey {
+    ADMIN_WALLET_PUBKEY_STR.parse().unwrap()
+}
+
+/// Validate a full set of config parameters before they are written to LotteryConfig
+fn validate_config_params(params: &ConfigParams) -> Result<()> {
+    require!(params.admin_wallet != Pubkey::default(), LotteryError::InvalidConfig);
+    require!(params.ticket_price_lamports > 0, LotteryError::InvalidConfig);
+    
+    // Ticket price must split exactly into prize pool + admin fee
+    let split_total = params.vault_amount_lamports
+        .checked_add(params.admin_amount_lamports)
+        .ok_or(LotteryError::MathOverflow)?;
+    require!(split_total == params.ticket_price_lamports, LotteryError::InvalidConfig);
+    
+    require!(
+        params.max_tickets_per_round > 0 && params.max_tickets_per_round <= MAX_TICKETS_PER_ROUND,
+        LotteryError::InvalidConfig
+    );
+    require!(params.round_duration_seconds > 0, LotteryError::InvalidConfig);
+    require!(params.timelock_delay_seconds >= 0, LotteryError::InvalidConfig);
+    require!(params.operator != Pubkey::default(), LotteryError::InvalidConfig);
+    require!(params.reveal_window_seconds > 0, LotteryError::InvalidConfig);
+    require!(params.referral_bps as u64 <= BPS_DENOMINATOR, LotteryError::InvalidConfig);
+    
+    // Fee recipients must cover exactly 100% of the fee so no lamports are left unassigned
+    let recipient_count = params.fee_recipient_count as usize;
+    require!(
+        (1..=MAX_FEE_RECIPIENTS).contains(&recipient_count),
+        LotteryError::InvalidFeeSplit
+    );
+    let mut total_bps: u64 = 0;
+    for recipient in &params.fee_recipients[..recipient_count] {
+        require!(
+            recipient.wallet != Pubkey::default() && recipient.bps > 0,
+            LotteryError::InvalidFeeSplit
+        );
+        total_bps += recipient.bps as u64;
     }
+    require!(total_bps == BPS_DENOMINATOR, LotteryError::InvalidFeeSplit);
     
-    let program_id = crate::ID;
-    let devnet_id: Pubkey = DEVNET_PROGRAM_ID.parse().unwrap();
+    Ok(())
+}
+
+/// Split a fee across recipients by basis points
+/// Floor rounding dust goes to the first recipient, so the shares always sum exactly to `total`
+fn split_fee(total: u64, recipients: &[FeeRecipient]) -> Result<[u64; MAX_FEE_RECIPIENTS]> {
+    let mut shares = [0u64; MAX_FEE_RECIPIENTS];
+    let mut assigned: u64 = 0;
     
-    if program_id == devnet_id {
-        ROUND_DURATION_DEVNET_SECONDS
-    } else {
-        ROUND_DURATION_MAINNET_SECONDS
+    for (share, recipient) in shares.iter_mut().zip(recipients) {
+        *share = (total as u128 * recipient.bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        assigned = assigned.checked_add(*share).ok_or(LotteryError::MathOverflow)?;
     }
+    shares[0] = shares[0]
+        .checked_add(total.checked_sub(assigned).ok_or(LotteryError::MathOverflow)?)
+        .ok_or(LotteryError::MathOverflow)?;
+    
+    Ok(shares)
 }
 
-fn get_admin_wallet_pubkey() -> Pubkey {
-    ADMIN_WALLET_PUBKEY_STR.parse().unwrap()
+/// Read the most recent (slot, hash) entry from the SlotHashes sysvar without deserializing all 512 entries
+/// Layout: u64 entry count, then (u64 slot, [u8; 32] hash) entries, most recent first
+fn most_recent_slot_hash(slot_hashes: &AccountInfo) -> Result<(u64, [u8; 32])> {
+    require!(
+        slot_hashes.key() == anchor_lang::solana_program::sysvar::slot_hashes::ID,
+        LotteryError::InvalidSlotHashes
+    );
+    
+    let data = slot_hashes.try_borrow_data()?;
+    require!(data.len() >= 8 + 8 + 32, LotteryError::InvalidSlotHashes);
+    
+    let count = u64::from_le_bytes(data[..8].try_into().unwrap());
+    require!(count > 0, LotteryError::InvalidSlotHashes);
+    
+    let slot = u64::from_le_bytes(data[8..16].try_into().unwrap());
+    let hash: [u8; 32] = data[16..48].try_into().unwrap();
+    
+    Ok((slot, hash))
+}
+
+/// Find the first bank at or after target_slot in SlotHashes (skipped slots have no entry)
+/// Returns None once that bank has left the 512-entry window; callers check the target was reached
+fn slot_hash_at_or_after(slot_hashes: &AccountInfo, target_slot: u64) -> Result<Option<(u64, [u8; 32])>> {
+    require!(
+        slot_hashes.key() == anchor_lang::solana_program::sysvar::slot_hashes::ID,
+        LotteryError::InvalidSlotHashes
+    );
+    
+    let data = slot_hashes.try_borrow_data()?;
+    require!(data.len() >= 8, LotteryError::InvalidSlotHashes);
+    let count = u64::from_le_bytes(data[..8].try_into().unwrap()) as usize;
+    
+    // Entries are (slot, hash), most recent first
+    let mut candidate = None;
+    for entry in data[8..].chunks_exact(8 + 32).take(count) {
+        let slot = u64::from_le_bytes(entry[..8].try_into().unwrap());
+        if slot < target_slot {
+            // The window reaches past target_slot, so the candidate is the first bank after it
+            return Ok(candidate);
+        }
+        candidate = Some((slot, entry[8..].try_into().unwrap()));
+        if slot == target_slot {
+            return Ok(candidate);
+        }
+    }
+    
+    // Every entry is newer than target_slot: the first bank at or after it is gone
+    Ok(None)
+}
+
+/// Fail with ProgramPaused if the given pause flag is set in config
+fn require_not_paused(config: &LotteryConfig, flag: u8) -> Result<()> {
+    require!(config.paused & flag == 0, LotteryError::ProgramPaused);
+    Ok(())
+}
+
+/// Upgrade a program-owned account in place to the current layout version
+/// v0 accounts (written before the version byte existed, exactly `v0_size` bytes) are rewritten by `upgrade_v0`;
+/// versioned accounts only grow: appended fields are zero-initialized and the version byte is bumped
+/// Returns the version the account was migrated from
+fn migrate_account<'info, T: AccountSerialize + Discriminator>(
+    accounts: &MigrateAccount<'info>,
+    program_id: &Pubkey,
+    v0_size: usize,
+    new_size: usize,
+    current_version: u8,
+    upgrade_v0: impl FnOnce(&[u8]) -> Result<T>,
+) -> Result<u8> {
+    let account = accounts.account.to_account_info();
+    let payer = accounts.payer.to_account_info();
+    let system_program = accounts.system_program.to_account_info();
+    
+    require!(
+        account.owner == program_id && account.is_writable,
+        LotteryError::InvalidMigrationAccount
+    );
+    
+    let (from_version, upgraded) = {
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == T::DISCRIMINATOR,
+            LotteryError::InvalidMigrationAccount
+        );
+        
+        if data.len() == v0_size {
+            (0, Some(upgrade_v0(&data[8..])?))
+        } else {
+            require!(data.len() > 8, LotteryError::InvalidMigrationAccount);
+            (data[8], None)
+        }
+    };
+    require!(from_version < current_version, LotteryError::AlreadyMigrated);
+    
+    // Top up rent for the larger layout, then grow the account (new bytes zeroed)
+    let required = Rent::get()?.minimum_balance(new_size);
+    let current = account.lamports();
+    if current < required {
+        anchor_lang::solana_program::program::invoke(
+            &anchor_lang::solana_program::system_instruction::transfer(
+                &payer.key(),
+                &account.key(),
+                required - current,
+            ),
+            &[payer, account.clone(), system_program],
+        )?;
+    }
+    account.realloc(new_size, true)?;
+    
+    let mut data = account.try_borrow_mut_data()?;
+    match upgraded {
+        Some(value) => value.try_serialize(&mut &mut data[..])?,
+        None => data[8] = current_version,
+    }
+    
+    Ok(from_version)
+}
+
+/// Validate a multisig signer set and threshold (1 <= threshold <= N, unique non-default keys)
+fn validate_multisig_signers(signers: &[Pubkey], threshold: u8) -> Result<()> {
+    require!(
+        !signers.is_empty() && signers.len() <= MAX_MULTISIG_SIGNERS,
+        LotteryError::InvalidMultisig
+    );
+    require!(
+        threshold >= 1 && threshold as usize <= signers.len(),
+        LotteryError::InvalidMultisig
+    );
+    
+    for (i, signer) in signers.iter().enumerate() {
+        require!(*signer != Pubkey::default(), LotteryError::InvalidMultisig);
+        require!(!signers[..i].contains(signer), LotteryError::InvalidMultisig);
+    }
+    
+    Ok(())
+}
+
+/// A TicketPosition can be closed once its round is settled (Ended / Claimed) and it is either
+/// a non-winning position or an already claimed one - the winner's rent stays until the prize is paid
+/// In a Cancelled round every position is owed a refund, so it can be closed once claim_refund paid it
+/// A round already removed by close_round had its prize claimed or every ticket refunded, so any position qualifies
+fn require_position_closable(
+    round_info: &AccountInfo,
+    position: &TicketPosition,
+    buyer: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<()> {
+    require!(position.round == round_info.key(), LotteryError::RoundNumberMismatch);
+    require!(position.buyer == *buyer, LotteryError::InvalidWinner);
+    
+    if round_info.owner != program_id {
+        require!(round_info.lamports() == 0, LotteryError::RoundNumberMismatch);
+        return Ok(());
+    }
+    
+    let round = Round::try_deserialize(&mut &round_info.try_borrow_data()?[..])?;
+    if round.status == RoundStatus::Cancelled {
+        require!(position.claimed, LotteryError::PositionNotSettled);
+        return Ok(());
+    }
+    require!(
+        round.status == RoundStatus::Ended || round.status == RoundStatus::Claimed,
+        LotteryError::RoundNotEnded
+    );
+    
+    let is_winner = round.winning_index.is_some_and(|winning_index| {
+        winning_index >= position.start_index
+            && winning_index - position.start_index < position.count as u64
+    });
+    require!(!is_winner || position.claimed, LotteryError::PositionNotSettled);
+    
+    Ok(())
+}
+
+/// Create a program-owned PDA of `space` bytes, signed with `seeds` (bump included); payer funds rent
+/// Same steps as Anchor's `init`: an address anyone pre-funded with lamports is still a system
+/// account, so it is topped up to rent exemption, allocated and assigned instead of create_account
+/// (which fails on a non-zero balance and would let a 1-lamport transfer block the PDA)
+fn create_pda_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    use anchor_lang::solana_program::{program::invoke, program::invoke_signed, system_instruction};
+    
+    let min_rent = Rent::get()?.minimum_balance(space);
+    let current_lamports = account.lamports();
+    
+    if current_lamports == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(&payer.key(), &account.key(), min_rent, space as u64, owner),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        )
+        .map_err(Into::into);
+    }
+    
+    require_keys_eq!(*account.owner, anchor_lang::solana_program::system_program::ID, ErrorCode::AccountNotSystemOwned);
+    
+    let top_up = min_rent.saturating_sub(current_lamports);
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(&payer.key(), &account.key(), top_up),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(&account.key(), space as u64),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(&account.key(), owner),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    
+    Ok(())
+}
+
+/// Load round PDA ["round", round_number], creating and opening it first if it does not exist yet
+/// Used where the round is only known at execution time (buy_tickets auto-roll); payer funds rent
+/// The caller writes the returned Round back with try_serialize
+fn load_or_create_round<'info>(
+    round_info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    program_id: &Pubkey,
+    round_number: u64,
+    bump: u8,
+    config: &LotteryConfig,
+) -> Result<Round> {
+    if round_info.owner == program_id {
+        return Round::try_deserialize(&mut &round_info.try_borrow_data()?[..]);
+    }
+    
+    // Anyone can send lamports to the future round address, so it may already hold a balance
+    let round_number_bytes = round_number.to_le_bytes();
+    create_pda_account(
+        round_info,
+        payer,
+        system_program,
+        Round::SIZE,
+        program_id,
+        &[b"round", round_number_bytes.as_ref(), &[bump]],
+    )?;
+    
+    let mut round = Round::default();
+    round.open(round_number, Clock::get()?.unix_timestamp, config, bump, payer.key());
+    round.try_serialize(&mut &mut round_info.try_borrow_mut_data()?[..])?;
+    
+    msg!("[LIFECYCLE] Round {} auto-created and active", round_number);
+    
+    Ok(round)
+}
+
+/// Cancel an Active or Drawing round passed through remaining_accounts (multisig CancelRound action)
+/// Buyers recover the prize-pool part of their tickets with claim_refund
+fn cancel_round<'info>(
+    round_info: &'info AccountInfo<'info>,
+    round_number: u64,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let (expected_round, _) = Pubkey::find_program_address(
+        &[b"round", round_number.to_le_bytes().as_ref()],
+        program_id,
+    );
+    require!(
+        round_info.key() == expected_round && round_info.is_writable,
+        LotteryError::InvalidProposalAccounts
+    );
+    
+    let mut round: Account<'info, Round> = Account::try_from(round_info)?;
+    // Drawing rounds can be cancelled too, e.g. when the oracle never fulfils
+    require!(
+        round.status == RoundStatus::Active || round.status == RoundStatus::Drawing,
+        LotteryError::RoundNotActive
+    );
+    
+    round.status = RoundStatus::Cancelled;
+    round.exit(program_id)?;
+    
+    msg!("Round {} cancelled", round_number);
+    
+    Ok(())
+}
+
+/// Withdraw lamports from the treasury PDA (multisig WithdrawTreasury action)
+/// Treasury seeds: ["treasury"] - SystemAccount PDA, list it in config.fee_recipients (or set it as
+/// config.admin_wallet for activation fees) to collect fees
+fn withdraw_treasury<'info>(
+    treasury_info: &AccountInfo<'info>,
+    recipient_info: &AccountInfo<'info>,
+    system_program_info: &AccountInfo<'info>,
+    recipient: Pubkey,
+    amount: u64,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let (expected_treasury, treasury_bump) = Pubkey::find_program_address(&[b"treasury"], program_id);
+    require!(
+        treasury_info.key() == expected_treasury && recipient_info.key() == recipient,
+        LotteryError::InvalidProposalAccounts
+    );
+    require!(treasury_info.lamports() >= amount, LotteryError::InsufficientTreasury);
+    
+    anchor_lang::solana_program::program::invoke_signed(
+        &anchor_lang::solana_program::system_instruction::transfer(
+            &expected_treasury,
+            &recipient,
+            amount,
+        ),
+        &[
+            treasury_info.clone(),
+            recipient_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[b"treasury", &[treasury_bump]]],
+    )?;
+    
+    msg!("Treasury withdrawal of {} lamports to {}", amount, recipient);
+    
+    Ok(())
 }
 
 #[program]
 pub mod sollottery {
     use super::*;
 
-    /// Activate user wallet - one-time 0.01 SOL fee
+    /// Initialize config - one-time creation of the LotteryConfig singleton
+    /// Seeds the config with the compiled-in defaults; only the default admin wallet may call it
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == get_admin_wallet_pubkey(),
+            LotteryError::Unauthorized
+        );
+
+        // Single fee recipient: the whole per-ticket fee goes to the admin wallet
+        let mut fee_recipients = [FeeRecipient::default(); MAX_FEE_RECIPIENTS];
+        fee_recipients[0] = FeeRecipient {
+            wallet: get_admin_wallet_pubkey(),
+            bps: BPS_DENOMINATOR as u16,
+        };
+
+        let config = &mut ctx.accounts.config;
+        config.version = LotteryConfig::VERSION;
+        config.authority = ctx.accounts.authority.key();
+        config.pending_authority = Pubkey::default();
+        config.paused = 0;
+        config.bump = ctx.bumps.config;
+        config.apply(&ConfigParams {
+            admin_wallet: get_admin_wallet_pubkey(),
+            ticket_price_lamports: TICKET_PRICE_LAMPORTS,
+            vault_amount_lamports: VAULT_AMOUNT_LAMPORTS,
+            admin_amount_lamports: ADMIN_AMOUNT_LAMPORTS,
+            activation_fee_lamports: ACTIVATION_FEE_LAMPORTS,
+            max_tickets_per_round: MAX_TICKETS_PER_ROUND,
+            round_duration_seconds: DEFAULT_ROUND_DURATION_SECONDS,
+            timelock_delay_seconds: DEFAULT_TIMELOCK_DELAY_SECONDS,
+            fee_recipients,
+            fee_recipient_count: 1,
+            operator: get_admin_wallet_pubkey(),
+            reveal_window_seconds: REVEAL_WINDOW_SECONDS,
+            oracle_program: Pubkey::default(),
+            activation_required: true,
+            referral_bps: 0,
+        });
+        config.pending_eta = 0;
+
+        msg!("Config initialized. Authority: {}", config.authority);
+
+        Ok(())
+    }
+
+    /// Initialize state - one-time creation of the global round sequencer (config authority only)
+    /// current_round_number: 1 on a fresh deployment, the live round when adopting existing rounds
+    pub fn initialize_state(ctx: Context<InitializeState>, current_round_number: u64) -> Result<()> {
+        require!(current_round_number > 0, LotteryError::InvalidConfig);
+
+        let state = &mut ctx.accounts.state;
+        state.version = LotteryState::VERSION;
+        state.current_round_number = current_round_number;
+        state.bump = ctx.bumps.state;
+
+        msg!("Round sequencer initialized at round {}", current_round_number);
+
+        Ok(())
+    }
+
+    /// Queue config change - authority stages a full replacement parameter set
+    /// It can be applied by execute_config_change once config.timelock_delay_seconds has passed
+    pub fn queue_config_change(ctx: Context<QueueConfigChange>, params: ConfigParams) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.config.queue_change(params, now)
+    }
+
+    /// Execute config change - permissionless once the queued change's timelock has expired
+    /// Rounds that are already Active keep the economics snapshotted when they opened
+    pub fn execute_config_change(ctx: Context<ExecuteConfigChange>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(config.pending_eta != 0, LotteryError::NoPendingConfigChange);
+        require!(now >= config.pending_eta, LotteryError::TimelockNotExpired);
+        
+        // Re-validate: the hard caps may have tightened since the change was queued
+        let params = config.pending_params.clone();
+        validate_config_params(&params)?;
+        config.apply(&params);
+        config.pending_eta = 0;
+
+        msg!(
+            "Config updated. ticket_price={}, vault_amount={}, admin_amount={}, max_tickets={}, round_duration={}",
+            config.ticket_price_lamports,
+            config.vault_amount_lamports,
+            config.admin_amount_lamports,
+            config.max_tickets_per_round,
+            config.round_duration_seconds
+        );
+
+        Ok(())
+    }
+
+    /// Cancel config change - authority discards the queued change
+    pub fn cancel_config_change(ctx: Context<QueueConfigChange>) -> Result<()> {
+        ctx.accounts.config.cancel_change()
+    }
+
+    /// Propose authority - first step of a two-step authority transfer
+    /// Proposing Pubkey::default() cancels a pending transfer
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.pending_authority = new_authority;
+
+        msg!("Authority transfer proposed: {} -> {}", config.authority, new_authority);
+
+        Ok(())
+    }
+
+    /// Accept authority - second step, signed by the proposed authority
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous_authority = config.authority;
+
+        config.authority = config.pending_authority;
+        config.pending_authority = Pubkey::default();
+
+        msg!("Authority transferred: {} -> {}", previous_authority, config.authority);
+
+        Ok(())
+    }
+
+    /// Set pause flags - emergency circuit breaker held by the config authority
+    /// Flags are independent: e.g. PAUSE_BUY_TICKETS alone freezes sales while claims stay open
+    pub fn set_pause_flags(ctx: Context<SetPauseFlags>, flags: u8) -> Result<()> {
+        require!(flags & !PAUSE_ALL == 0, LotteryError::InvalidConfig);
+        
+        let config = &mut ctx.accounts.config;
+        config.paused = flags;
+        
+        msg!("Pause flags set to {:#06b}", flags);
+        
+        Ok(())
+    }
+
+    /// Create multisig - switches the config into M-of-N multisig mode
+    /// The Multisig PDA becomes config.authority, so privileged actions then require executed proposals
+    pub fn create_multisig(ctx: Context<CreateMultisig>, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        validate_multisig_signers(&signers, threshold)?;
+        
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.version = Multisig::VERSION;
+        multisig.set_signers(&signers, threshold);
+        multisig.signer_set_seqno = 0;
+        multisig.proposal_count = 0;
+        multisig.bump = ctx.bumps.multisig;
+        
+        let config = &mut ctx.accounts.config;
+        config.authority = multisig.key();
+        config.pending_authority = Pubkey::default();
+        
+        msg!(
+            "Multisig created: {}-of-{}, config authority is now {}",
+            threshold,
+            signers.len(),
+            config.authority
+        );
+        
+        Ok(())
+    }
+
+    /// Create proposal - a multisig signer proposes a privileged action (counts as their approval)
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: ProposalAction) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let signer_index = multisig
+            .signer_index(&ctx.accounts.proposer.key())
+            .ok_or(LotteryError::NotMultisigSigner)?;
+        
+        action.validate()?;
+        
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.version = Proposal::VERSION;
+        proposal.index = multisig.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.action = action;
+        proposal.approvals = 1 << signer_index;
+        proposal.signer_set_seqno = multisig.signer_set_seqno;
+        proposal.executed = false;
+        proposal.bump = ctx.bumps.proposal;
+        
+        multisig.proposal_count = multisig.proposal_count
+            .checked_add(1)
+            .ok_or(LotteryError::MathOverflow)?;
+        
+        msg!("Proposal {} created by {}", proposal.index, proposal.proposer);
+        
+        Ok(())
+    }
+
+    /// Approve proposal - a multisig signer adds their approval
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let proposal = &mut ctx.accounts.proposal;
+        
+        let signer_index = multisig
+            .signer_index(&ctx.accounts.signer.key())
+            .ok_or(LotteryError::NotMultisigSigner)?;
+        require!(!proposal.executed, LotteryError::ProposalAlreadyExecuted);
+        require!(
+            proposal.signer_set_seqno == multisig.signer_set_seqno,
+            LotteryError::ProposalStale
+        );
+        
+        let bit = 1u16 << signer_index;
+        require!(proposal.approvals & bit == 0, LotteryError::AlreadyApproved);
+        proposal.approvals |= bit;
+        
+        msg!(
+            "Proposal {} approved by {} ({}/{})",
+            proposal.index,
+            ctx.accounts.signer.key(),
+            proposal.approvals.count_ones(),
+            multisig.threshold
+        );
+        
+        Ok(())
+    }
+
+    /// Execute proposal - any multisig signer executes once the threshold is met
+    /// Action-specific accounts are passed through remaining_accounts:
+    /// - CancelRound: [round (mut)]
+    /// - WithdrawTreasury: [treasury (mut), recipient (mut)]
+    pub fn execute_proposal<'info>(ctx: Context<'_, '_, 'info, 'info, ExecuteProposal<'info>>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let proposal = &mut ctx.accounts.proposal;
+        
+        require!(
+            multisig.signer_index(&ctx.accounts.signer.key()).is_some(),
+            LotteryError::NotMultisigSigner
+        );
+        require!(!proposal.executed, LotteryError::ProposalAlreadyExecuted);
+        require!(
+            proposal.signer_set_seqno == multisig.signer_set_seqno,
+            LotteryError::ProposalStale
+        );
+        require!(
+            proposal.approvals.count_ones() >= multisig.threshold as u32,
+            LotteryError::NotEnoughApprovals
+        );
+        
+        // Mark executed before dispatch so the proposal cannot be replayed
+        proposal.executed = true;
+        
+        match proposal.action.clone() {
+            ProposalAction::QueueConfigChange(params) => {
+                let now = Clock::get()?.unix_timestamp;
+                ctx.accounts.config.queue_change(params, now)?;
+            }
+            ProposalAction::CancelConfigChange => {
+                ctx.accounts.config.cancel_change()?;
+            }
+            ProposalAction::ProposeAuthority { new_authority } => {
+                ctx.accounts.config.pending_authority = new_authority;
+            }
+            ProposalAction::SetPauseFlags { flags } => {
+                require!(flags & !PAUSE_ALL == 0, LotteryError::InvalidConfig);
+                ctx.accounts.config.paused = flags;
+            }
+            ProposalAction::ChangeThreshold { threshold } => {
+                let signer_count = multisig.signer_count as usize;
+                validate_multisig_signers(&multisig.signers[..signer_count], threshold)?;
+                multisig.threshold = threshold;
+                // A lower threshold must not make older, under-approved proposals executable
+                multisig.signer_set_seqno = multisig.signer_set_seqno
+                    .checked_add(1)
+                    .ok_or(LotteryError::MathOverflow)?;
+            }
+            ProposalAction::ChangeSigners { signers, signer_count, threshold } => {
+                let signers = &signers[..signer_count as usize];
+                validate_multisig_signers(signers, threshold)?;
+                multisig.set_signers(signers, threshold);
+                // Invalidate every pending proposal approved under the old signer set
+                multisig.signer_set_seqno = multisig.signer_set_seqno
+                    .checked_add(1)
+                    .ok_or(LotteryError::MathOverflow)?;
+            }
+            ProposalAction::WithdrawTreasury { recipient, amount } => {
+                require!(
+                    ctx.remaining_accounts.len() == 2,
+                    LotteryError::InvalidProposalAccounts
+                );
+                withdraw_treasury(
+                    &ctx.remaining_accounts[0],
+                    &ctx.remaining_accounts[1],
+                    &ctx.accounts.system_program.to_account_info(),
+                    recipient,
+                    amount,
+                    ctx.program_id,
+                )?;
+            }
+            ProposalAction::CancelRound { round_number } => {
+                require!(
+                    ctx.remaining_accounts.len() == 1,
+                    LotteryError::InvalidProposalAccounts
+                );
+                cancel_round(&ctx.remaining_accounts[0], round_number, ctx.program_id)?;
+            }
+        }
+        
+        msg!("Proposal {} executed", proposal.index);
+        
+        Ok(())
+    }
+
+    /// Activate user wallet - one-time activation fee (config.activation_fee_lamports)
+    /// An optional referrer_profile sets the referrer (once - activation itself happens once)
     pub fn activate_user(ctx: Context<ActivateUser>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_ACTIVATION)?;
+        
         require!(
-            ctx.accounts.admin_wallet.key() == get_admin_wallet_pubkey(),
+            ctx.accounts.admin_wallet.key() == ctx.accounts.config.admin_wallet,
             LotteryError::InvalidAdminWallet
         );
         
@@ -67,7 +802,7 @@ pub mod sollottery {
             &anchor_lang::solana_program::system_instruction::transfer(
                 &ctx.accounts.user.key(),
                 &ctx.accounts.admin_wallet.key(),
-                ACTIVATION_FEE_LAMPORTS,
+                ctx.accounts.config.activation_fee_lamports,
             ),
             &[
                 ctx.accounts.user.to_account_info(),
@@ -76,8 +811,28 @@ pub mod sollottery {
             ],
         )?;
         
+        user_profile.version = UserProfile::VERSION;
         user_profile.user = ctx.accounts.user.key();
         user_profile.activated = true;
+        if user_profile.activated_at == 0 {
+            user_profile.activated_at = Clock::get()?.unix_timestamp;
+        }
+        
+        // REFERRAL: the referrer earns config.referral_bps of this user's ticket fees
+        if let Some(referrer_profile) = ctx.accounts.referrer_profile.as_mut() {
+            require!(
+                user_profile.referrer == Pubkey::default()
+                    && referrer_profile.activated
+                    && referrer_profile.user != user_profile.user,
+                LotteryError::InvalidReferrer
+            );
+            user_profile.referrer = referrer_profile.user;
+            referrer_profile.referred_users = referrer_profile.referred_users
+                .checked_add(1)
+                .ok_or(LotteryError::MathOverflow)?;
+            
+            msg!("User {} referred by {}", user_profile.user, user_profile.referrer);
+        }
         
         msg!("User {} activated", ctx.accounts.user.key());
         
@@ -86,24 +841,47 @@ pub mod sollottery {
 
 
     /// Buy tickets - PURE ON-CHAIN LIFECYCLE with SCALABLE ACCOUNT MODEL
-    /// Automatically handles round lifecycle: creates round 1 if needed, ends expired rounds, creates next round
+    /// Automatically handles round lifecycle: creates round 1 if needed, ends expired zero-ticket rounds and
+    /// rolls the purchase of a settled round (Ended, Claimed or Cancelled) into round_number + 1 (created if
+    /// needed) in the same transaction
     /// Uses fixed-size TicketPosition accounts - one per purchase, no Vec, no reallocations
-    pub fn buy_tickets(ctx: Context<BuyTickets>, round_number: u64, ticket_count: u8) -> Result<()> {
+    /// remaining_accounts: the purchased round's fee recipient wallets (mut), in snapshot order
+    /// (config.fee_recipients for a round this purchase opens)
+    /// client_entropy: 32 random bytes from the buyer's client, folded into round.entropy_accumulator
+    pub fn buy_tickets<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BuyTickets<'info>>,
+        round_number: u64,
+        ticket_count: u8,
+        client_entropy: [u8; 32],
+    ) -> Result<()> {
         msg!("=== buy_tickets START (SCALABLE MODEL) ===");
         msg!("round_number = {}", round_number);
         msg!("ticket_count = {}", ticket_count);
         msg!("buyer = {}", ctx.accounts.buyer.key());
         
-        require!(ticket_count == 1, LotteryError::InvalidTicketCount);
+        require!(
+            (1..=MAX_TICKETS_PER_TX).contains(&ticket_count),
+            LotteryError::InvalidTicketCount
+        );
+        
+        let config = &ctx.accounts.config;
+        require_not_paused(config, PAUSE_BUY_TICKETS)?;
         
-        // Verify admin wallet
+        // SEQUENCER: only the canonical round is accepted (or the one it just rolled over from,
+        // so clients racing a roll-over still land in the current round below)
+        let current_round_number = ctx.accounts.state.current_round_number;
         require!(
-            ctx.accounts.admin_wallet.key() == get_admin_wallet_pubkey(),
-            LotteryError::InvalidAdminWallet
+            round_number == current_round_number || round_number.checked_add(1) == Some(current_round_number),
+            LotteryError::NotCurrentRound
         );
         
-        let clock = Clock::get()?;
-        let now = clock.unix_timestamp;
+        // ACTIVATION GATE: buyers must have paid the activation fee unless the config turns it off
+        require!(
+            !config.activation_required || ctx.accounts.user_profile.activated,
+            LotteryError::UserNotActivated
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
         
         // ========== ON-CHAIN LIFECYCLE LOGIC ==========
         // Step 1: Auto-create round if it doesn't exist (AUTONOMOUS LIFECYCLE)
@@ -115,14 +893,8 @@ pub mod sollottery {
         if current_round.round_number == 0 || current_round.round_number != round_number {
             msg!("[LIFECYCLE] Auto-creating round {} (current: {})", round_number, current_round.round_number);
             
-            // Initialize round with proper values
-            current_round.round_number = round_number;
-            current_round.start_timestamp = now;
-            current_round.end_timestamp = now + get_round_duration();
-            current_round.total_tickets = 0;
-            current_round.winning_index = None;
-            current_round.status = RoundStatus::Active;
-            current_round.bump = ctx.bumps.current_round;
+            // Initialize round with proper values (snapshots the current economics)
+            current_round.open(round_number, now, config, ctx.bumps.current_round, ctx.accounts.buyer.key());
             
             // Initialize vault if it doesn't exist
             // SystemAccount constraint ensures vault is owned by SystemProgram, but we need to create it if it doesn't exist
@@ -156,71 +928,128 @@ pub mod sollottery {
             msg!("[LIFECYCLE] Round {} auto-created and active", round_number);
         }
         
-        // Step 2: Check if current round is expired or already ended - auto-end/auto-roll (AUTONOMOUS LIFECYCLE)
-        if current_round.status == RoundStatus::Active && now >= current_round.end_timestamp {
-            msg!("[LIFECYCLE] Current round {} expired, auto-ending", current_round.round_number);
+        // Step 2: Auto-end an expired zero-ticket round (AUTONOMOUS LIFECYCLE) - persisted, the purchase rolls over below
+        // Rounds with tickets are drawn by request_draw; they stay Active and the purchase fails RoundExpired below
+        // Finalization is paused: the round stays Active as well
+        if current_round.status == RoundStatus::Active
+            && now >= current_round.end_timestamp
+            && current_round.total_tickets == 0
+            && config.paused & PAUSE_FINALIZE == 0
+        {
+            current_round.winning_index = None;
+            current_round.status = RoundStatus::Ended;
+            msg!("[LIFECYCLE] Round {} expired with zero tickets, auto-ended", current_round.round_number);
+        }
+        
+        // Step 3: Pick the round for this purchase - the requested round while it is Active,
+        // AUTO-ROLL into round_number + 1 once it is settled (created if needed, advances the sequencer)
+        // Same rule as start_next_round: only Ended, Claimed or Cancelled rounds give up the sequencer,
+        // a Drawing round fails RoundNotActive below
+        let mut rolled_round = None;
+        if matches!(
+            current_round.status,
+            RoundStatus::Ended | RoundStatus::Claimed | RoundStatus::Cancelled
+        ) {
+            let next_round_number = round_number.checked_add(1).ok_or(LotteryError::MathOverflow)?;
+            msg!("[LIFECYCLE] Round {} closed, rolling purchase into round {}", round_number, next_round_number);
             
-            // AUTONOMOUS LIFECYCLE: Auto-end expired round (handles zero-ticket case)
-            if current_round.total_tickets == 0 {
-                // Zero tickets - end without winner
-                current_round.winning_index = None;
-                current_round.status = RoundStatus::Ended;
-                msg!("[LIFECYCLE] Round {} ended with zero tickets", current_round.round_number);
-            } else {
-                // Has tickets - compute winning index
-                let mut hash: u64 = clock.slot;
-                hash = hash.wrapping_mul(31).wrapping_add(now as u64);
-                hash = hash.wrapping_mul(31).wrapping_add(current_round.round_number);
-                let winning_index = hash % current_round.total_tickets;
-                current_round.winning_index = Some(winning_index);
-                current_round.status = RoundStatus::Ended;
-                msg!("[LIFECYCLE] Round {} ended. Winning index: {}", current_round.round_number, winning_index);
-            }
+            rolled_round = Some(load_or_create_round(
+                &ctx.accounts.next_round.to_account_info(),
+                &ctx.accounts.buyer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                ctx.program_id,
+                next_round_number,
+                ctx.bumps.next_round,
+                config,
+            )?);
             
-            // Round expired - return error indicating frontend should retry with next round
-            return Err(LotteryError::RoundExpired.into());
+            // The previous round is closed, so its successor becomes the canonical round
+            if next_round_number > current_round_number {
+                ctx.accounts.state.current_round_number = next_round_number;
+            }
         }
+        require!(
+            rolled_round.is_some() || round_number == current_round_number,
+            LotteryError::NotCurrentRound
+        );
+        let current_round_key = ctx.accounts.current_round.key();
+        let (round, round_key, vault, vault_bump): (&mut Round, Pubkey, _, _) = match rolled_round.as_mut() {
+            Some(next_round) => (
+                next_round,
+                ctx.accounts.next_round.key(),
+                &ctx.accounts.next_vault,
+                ctx.bumps.next_vault,
+            ),
+            None => (
+                &mut ctx.accounts.current_round,
+                current_round_key,
+                &ctx.accounts.current_vault,
+                ctx.bumps.current_vault,
+            ),
+        };
+        
+        // Verify round is open (AUTONOMOUS LIFECYCLE ensures we normally have an active round)
+        require!(round.status == RoundStatus::Active, LotteryError::RoundNotActive);
+        require!(now < round.end_timestamp, LotteryError::RoundExpired);
         
-        // Step 3: Verify round is active (AUTONOMOUS LIFECYCLE ensures we have an active round)
-        // After Step 1, current_round.round_number == round_number, so just check status
+        // Verify fee recipient accounts against the round's fee split (remaining_accounts, in order)
+        let (fee_recipients, recipient_count, referral_bps) = round.fee_split(config);
         require!(
-            current_round.status == RoundStatus::Active,
-            LotteryError::RoundNotActive
+            ctx.remaining_accounts.len() == recipient_count,
+            LotteryError::InvalidFeeRecipient
         );
+        for (account, recipient) in ctx.remaining_accounts.iter().zip(&fee_recipients[..recipient_count]) {
+            require!(
+                account.key() == recipient.wallet && account.is_writable,
+                LotteryError::InvalidFeeRecipient
+            );
+        }
         
         // ========== TICKET PURCHASE LOGIC (SCALABLE MODEL) ==========
+        // Amounts for the whole range (ticket_count tickets, one TicketPosition)
+        let vault_total = round.vault_amount_lamports
+            .checked_mul(ticket_count as u64)
+            .ok_or(LotteryError::MathOverflow)?;
+        let fee_total = round.admin_amount_lamports
+            .checked_mul(ticket_count as u64)
+            .ok_or(LotteryError::MathOverflow)?;
+        
         // Check buyer balance
         let buyer_balance_before = ctx.accounts.buyer.lamports();
-        let total_needed = VAULT_AMOUNT_LAMPORTS + ADMIN_AMOUNT_LAMPORTS;
+        let total_needed = round.ticket_price_lamports
+            .checked_mul(ticket_count as u64)
+            .ok_or(LotteryError::MathOverflow)?;
         require!(buyer_balance_before >= total_needed, LotteryError::MathOverflow);
         
-        // Enforce hard cap: MAX_TICKETS_PER_ROUND = 1000
-        // This prevents round from exceeding capacity
+        // Enforce hard cap: the round's max_tickets_per_round (never above MAX_TICKETS_PER_ROUND)
+        // The whole range must fit - no partial fills
+        let tickets_after = round.total_tickets
+            .checked_add(ticket_count as u64)
+            .ok_or(LotteryError::MathOverflow)?;
         require!(
-            current_round.total_tickets + ticket_count as u64 <= MAX_TICKETS_PER_ROUND as u64,
+            tickets_after <= round.max_tickets(config),
             LotteryError::RoundSoldOut
         );
         
         // Initialize vault if needed and verify ownership
-        if ctx.accounts.current_vault.lamports() == 0 {
+        if vault.lamports() == 0 {
             let rent = Rent::get()?;
             let min_rent = rent.minimum_balance(0);
-            let vault_bump = ctx.bumps.current_vault;
-            let round_number_bytes = round_number.to_le_bytes();
+            let round_number_bytes = round.round_number.to_le_bytes();
             let seeds = &[b"vault", round_number_bytes.as_ref(), &[vault_bump]];
             
             // Create SystemAccount PDA - vault is owned by SystemProgram
             anchor_lang::solana_program::program::invoke_signed(
                 &anchor_lang::solana_program::system_instruction::create_account(
                     &ctx.accounts.buyer.key(),  // Payer (buyer pays rent)
-                    &ctx.accounts.current_vault.key(),  // New account (PDA)
+                    &vault.key(),  // New account (PDA)
                     min_rent,  // Rent exemption
                     0,  // Data size (system account)
                     &anchor_lang::solana_program::system_program::ID,  // Owner (SystemProgram)
                 ),
                 &[
                     ctx.accounts.buyer.to_account_info(),
-                    ctx.accounts.current_vault.to_account_info(),
+                    vault.to_account_info(),
                     ctx.accounts.system_program.to_account_info(),
                 ],
                 &[&seeds[..]],
@@ -229,17 +1058,17 @@ pub mod sollottery {
         
         // CRITICAL: Verify vault is owned by SystemProgram before transfers
         require!(
-            ctx.accounts.current_vault.owner == &anchor_lang::solana_program::system_program::ID,
+            vault.owner == &anchor_lang::solana_program::system_program::ID,
             LotteryError::InvalidVaultBalance
         );
         
         // Verify vault PDA derivation matches
         let (expected_vault, _) = Pubkey::find_program_address(
-            &[b"vault", round_number.to_le_bytes().as_ref()],
+            &[b"vault", round.round_number.to_le_bytes().as_ref()],
             ctx.program_id,
         );
         require!(
-            ctx.accounts.current_vault.key() == expected_vault,
+            vault.key() == expected_vault,
             LotteryError::InvalidVaultBalance
         );
         
@@ -248,44 +1077,83 @@ pub mod sollottery {
         anchor_lang::solana_program::program::invoke(
             &anchor_lang::solana_program::system_instruction::transfer(
                 &ctx.accounts.buyer.key(),
-                &ctx.accounts.current_vault.key(),
-                VAULT_AMOUNT_LAMPORTS,
+                &vault.key(),
+                vault_total,
             ),
             &[
                 ctx.accounts.buyer.to_account_info(),
-                ctx.accounts.current_vault.to_account_info(),
+                vault.to_account_info(),
                 ctx.accounts.system_program.to_account_info(),
             ],
         )?;
         
-        // Transfer to admin
-        anchor_lang::solana_program::program::invoke(
-            &anchor_lang::solana_program::system_instruction::transfer(
-                &ctx.accounts.buyer.key(),
-                &ctx.accounts.admin_wallet.key(),
-                ADMIN_AMOUNT_LAMPORTS,
-            ),
-            &[
-                ctx.accounts.buyer.to_account_info(),
-                ctx.accounts.admin_wallet.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
+        // REFERRAL: the referrer's share of the fee accrues in their UserProfile (claim_referral_rewards)
+        let referrer = ctx.accounts.user_profile.referrer;
+        let mut referral_share = 0;
+        if referrer != Pubkey::default() {
+            let referrer_profile = ctx.accounts.referrer_profile.as_mut().ok_or(LotteryError::InvalidReferrer)?;
+            require!(referrer_profile.user == referrer, LotteryError::InvalidReferrer);
+            
+            referral_share = (fee_total as u128 * referral_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+            if referral_share > 0 {
+                anchor_lang::solana_program::program::invoke(
+                    &anchor_lang::solana_program::system_instruction::transfer(
+                        &ctx.accounts.buyer.key(),
+                        &referrer_profile.key(),
+                        referral_share,
+                    ),
+                    &[
+                        ctx.accounts.buyer.to_account_info(),
+                        referrer_profile.to_account_info(),
+                        ctx.accounts.system_program.to_account_info(),
+                    ],
+                )?;
+            }
+            referrer_profile.record_referral(ticket_count as u64, referral_share)?;
+        }
         
-        // ========== CREATE TICKET POSITION (SCALABLE MODEL) ==========
+        // Split the rest of the fee across the fee recipients (vault + referral + fee shares == ticket price)
+        let shares = split_fee(
+            fee_total - referral_share,
+            &fee_recipients[..recipient_count],
+        )?;
+        for (recipient_info, share) in ctx.remaining_accounts.iter().zip(shares) {
+            if share == 0 {
+                continue;
+            }
+            anchor_lang::solana_program::program::invoke(
+                &anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.buyer.key(),
+                    &recipient_info.key(),
+                    share,
+                ),
+                &[
+                    ctx.accounts.buyer.to_account_info(),
+                    recipient_info.clone(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+        
+        // ========== CREATE TICKET POSITION (SCALABLE MODEL) ==========
         // Get start_index from round.total_tickets (before incrementing)
-        let start_index = current_round.total_tickets;
+        let start_index = round.total_tickets;
         
         // Derive TicketPosition PDA manually
-        // Seeds: ["ticket", round.key(), buyer.key(), round.total_tickets.to_le_bytes()]
-        let round_key = current_round.key();
+        // Seeds: ["ticket", buyer.key(), user_profile.purchase_count.to_le_bytes()]
+        // Per-buyer nonce - concurrent buyers never race on the seeds; start_index is assigned here
         let buyer_key = ctx.accounts.buyer.key();
-        let nonce_bytes = start_index.to_le_bytes();
+        let user_profile = &mut ctx.accounts.user_profile;
+        if user_profile.user == Pubkey::default() {
+            // First purchase created the profile (not activated, only possible with the gate off)
+            user_profile.version = UserProfile::VERSION;
+            user_profile.user = buyer_key;
+        }
+        let nonce_bytes = user_profile.purchase_count.to_le_bytes();
         
         let (expected_pda, bump) = Pubkey::find_program_address(
             &[
                 b"ticket",
-                round_key.as_ref(),
                 buyer_key.as_ref(),
                 &nonce_bytes,
             ],
@@ -295,52 +1163,24 @@ pub mod sollottery {
         // Verify the PDA matches what was passed
         require!(
             ctx.accounts.ticket_position.key() == expected_pda,
-            LotteryError::InvalidVaultBalance // TODO: Add proper error
+            LotteryError::InvalidTicketPosition
         );
         
-        // Initialize TicketPosition account using system_instruction (NO direct lamport mutation)
+        // Create the TicketPosition PDA - buyer pays rent, program signs for the PDA using seeds + bump
+        // The nonce is fresh, so the account never holds data; a pre-funded address is topped up instead
         let ticket_position_info = ctx.accounts.ticket_position.to_account_info();
-        let rent = Rent::get()?;
-        let min_rent = rent.minimum_balance(TicketPosition::SIZE);
-        
-        // Create TicketPosition account if it doesn't exist using invoke_signed
-        if ticket_position_info.lamports() == 0 {
-            // Derive seeds for TicketPosition PDA
-            let ticket_seeds = &[
-                b"ticket",
-                round_key.as_ref(),
-                buyer_key.as_ref(),
-                &nonce_bytes,
-            ];
-            
-            // Create TicketPosition PDA account using invoke_signed
-            // Buyer pays rent, account is owned by program (PDA)
-            // Program signs for the PDA using seeds + bump
-            anchor_lang::solana_program::program::invoke_signed(
-                &anchor_lang::solana_program::system_instruction::create_account(
-                    &ctx.accounts.buyer.key(),  // Payer (buyer pays rent)
-                    &ctx.accounts.ticket_position.key(),  // New account (PDA)
-                    min_rent,  // Rent exemption amount
-                    TicketPosition::SIZE as u64,  // Account data size
-                    ctx.program_id,  // Owner (program owns the PDA)
-                ),
-                &[
-                    ctx.accounts.buyer.to_account_info(),
-                    ticket_position_info.clone(),
-                    ctx.accounts.system_program.to_account_info(),
-                ],
-                &[&[
-                    b"ticket",
-                    round_key.as_ref(),
-                    buyer_key.as_ref(),
-                    &nonce_bytes,
-                    &[bump],
-                ]],
-            )?;
-        }
+        create_pda_account(
+            &ticket_position_info,
+            &ctx.accounts.buyer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            TicketPosition::SIZE,
+            ctx.program_id,
+            &[b"ticket", buyer_key.as_ref(), &nonce_bytes, &[bump]],
+        )?;
         
         // Serialize and write TicketPosition data
         let ticket_position = TicketPosition {
+            version: TicketPosition::VERSION,
             round: round_key,
             buyer: buyer_key,
             start_index,
@@ -350,43 +1190,79 @@ pub mod sollottery {
         };
         
         // Use Anchor's account serialization
-        ticket_position.try_serialize(&mut &mut *ticket_position_info.try_borrow_mut_data()?)?;
+        ticket_position.try_serialize(&mut &mut ticket_position_info.try_borrow_mut_data()?[..])?;
         
-        // Increment round.total_tickets safely (after TicketPosition created)
-        current_round.total_tickets = current_round.total_tickets
-            .checked_add(ticket_count as u64)
+        // Increment round.total_tickets and the buyer nonce (after TicketPosition created)
+        round.total_tickets = tickets_after;
+        user_profile.purchase_count = user_profile.purchase_count
+            .checked_add(1)
             .ok_or(LotteryError::MathOverflow)?;
+        user_profile.record_purchase(round.round_number, ticket_count as u64, total_needed)?;
+        
+        // Fold the buyer's entropy into the round accumulator (mixed into the slot hash draw)
+        round.entropy_accumulator =
+            draw::accumulate_entropy(&round.entropy_accumulator, &buyer_key, &client_entropy);
         
         msg!("=== buy_tickets SUCCESS ===");
         msg!("Created TicketPosition: start_index={}, count={}, total_tickets={}", 
-             start_index, ticket_count, current_round.total_tickets);
+             start_index, ticket_count, round.total_tickets);
+        
+        // The rolled round is not a context account - write it back explicitly
+        if let Some(next_round) = rolled_round {
+            next_round.try_serialize(&mut &mut ctx.accounts.next_round.try_borrow_mut_data()?[..])?;
+        }
+        
         Ok(())
     }
 
-    /// Finalize round - permissionless round finalization (PROOF-BASED, NO LOOPS)
+    /// Request draw - permissionless first phase of round finalization (PROOF-BASED, NO LOOPS)
     /// AUTONOMOUS LIFECYCLE: Callable by anyone, handles zero-ticket rounds, never stalls
-    /// O(1) complexity - no iteration, no Vec operations
+    /// Binds the draw to the hash of a future slot (N + DRAW_TARGET_SLOT_OFFSET); settle_draw completes it
+    /// (reveal_round_secret for committed rounds)
+    /// Also re-requests a Drawing round whose target slot hash expired from SlotHashes, and cancels a
+    /// committed round whose secret was not revealed in time or an oracle draw that was not fulfilled in time
     /// IDEMPOTENT: Can be called multiple times safely (status check prevents re-execution)
-    pub fn finalize_round(ctx: Context<FinalizeRound>) -> Result<()> {
-        let round = &mut ctx.accounts.round;
-        
-        // Require round is active (ensures idempotency - can't finalize already-ended round)
-        require!(round.status == RoundStatus::Active, LotteryError::RoundNotActive);
+    pub fn request_draw(ctx: Context<RequestDraw>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_FINALIZE)?;
         
+        let round = &mut ctx.accounts.round;
         let clock = Clock::get()?;
         let now = clock.unix_timestamp;
         
+        // Require round is active, has an expired slot or oracle draw (can't re-request a pending or ended
+        // draw) or is a committed round awaiting its reveal (cancel fallback below)
+        require!(
+            round.status == RoundStatus::Active
+                || round.slot_draw_expired()
+                || round.oracle_draw_expired(now)
+                || (round.status == RoundStatus::Drawing && round.has_secret_commitment()),
+            LotteryError::RoundNotActive
+        );
+        
         // Require round has expired (permissionless check)
         require!(
             now >= round.end_timestamp,
             LotteryError::RoundNotExpired // This maps to RoundStillRunning in error codes if needed
         );
         
+        // ORACLE TIMEOUT: a request the oracle never fulfilled falls back to the refund path
+        if round.oracle_draw_expired(now) {
+            round.status = RoundStatus::Cancelled;
+            
+            msg!(
+                "Round {} cancelled - oracle request {} not fulfilled in time, buyers can claim refunds",
+                round.round_number,
+                round.oracle_request
+            );
+            
+            return Ok(());
+        }
+        
         // AUTONOMOUS LIFECYCLE: Handle zero-ticket rounds by extending the round
         if round.total_tickets == 0 {
             // Zero tickets - extend the round instead of ending it
             // This prevents dead rounds and keeps the lottery active
-            let new_end_timestamp = now + get_round_duration();
+            let new_end_timestamp = now + ctx.accounts.config.round_duration_seconds;
             round.end_timestamp = new_end_timestamp;
             // Keep round.status = Active (don't change it)
             // Don't set winning_index (keep as None)
@@ -400,24 +1276,226 @@ pub mod sollottery {
             return Ok(());
         }
         
-        // Compute winning_index using hashv (deterministic, O(1))
-        // Use round_number and slot for randomness
-        let seed = hashv(&[
-            round.round_number.to_le_bytes().as_ref(),
-            clock.slot.to_le_bytes().as_ref(),
-        ]);
+        // COMMIT-REVEAL: a committed round is drawn only by reveal_round_secret, against the hash
+        // of a slot fixed here once - the operator cannot pick the slot by timing the reveal
+        // If the operator misses the reveal deadline, the round falls back to the refund path
+        if round.has_secret_commitment() {
+            let reveal_deadline = round.reveal_deadline(&ctx.accounts.config)?;
+            
+            if now > reveal_deadline {
+                round.status = RoundStatus::Cancelled;
+                
+                msg!(
+                    "Round {} cancelled - secret not revealed by {}, buyers can claim refunds",
+                    round.round_number,
+                    reveal_deadline
+                );
+                
+                return Ok(());
+            }
+            
+            require!(round.status == RoundStatus::Active, LotteryError::RevealPending);
+            let target_slot = round.request_slot_draw(clock.slot)?;
+            
+            msg!(
+                "Round {} drawing - operator reveal is mixed with the hash of slot {}",
+                round.round_number,
+                target_slot
+            );
+            
+            return Ok(());
+        }
+        
+        // ORACLE MODE: request randomness and park the round in Drawing until consume_randomness
+        if ctx.accounts.config.oracle_program != Pubkey::default() {
+            let oracle_program = ctx.accounts.oracle_program.as_ref().ok_or(LotteryError::OracleAccountsMissing)?;
+            let oracle_request = ctx.accounts.oracle_request.as_ref().ok_or(LotteryError::OracleAccountsMissing)?;
+            let payer = ctx.accounts.payer.as_ref().ok_or(LotteryError::OracleAccountsMissing)?;
+            require!(
+                oracle_program.key() == ctx.accounts.config.oracle_program,
+                LotteryError::InvalidOracleRequest
+            );
+            
+            // One request per round: the round PDA is both requester and seed
+            let round_key = round.key();
+            let round_number_bytes = round.round_number.to_le_bytes();
+            let round_seeds: &[&[u8]] = &[b"round", round_number_bytes.as_ref(), &[round.bump]];
+            
+            anchor_lang::solana_program::program::invoke_signed(
+                &oracle::request_randomness(
+                    &oracle_program.key(),
+                    &oracle_request.key(),
+                    &round_key,
+                    &payer.key(),
+                    round_key.to_bytes(),
+                ),
+                &[
+                    oracle_request.to_account_info(),
+                    round.to_account_info(),
+                    payer.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                    oracle_program.to_account_info(),
+                ],
+                &[round_seeds],
+            )?;
+            
+            round.request_oracle_draw(oracle_request.key(), now);
+            
+            msg!(
+                "Round {} drawing - randomness requested from oracle ({})",
+                round.round_number,
+                round.oracle_request
+            );
+            
+            return Ok(());
+        }
+        
+        // SLOT HASH DRAW: bind the draw to a slot that does not exist yet
+        // Nobody can pick the slot, and its hash cannot predate end_timestamp
+        let target_slot = round.request_slot_draw(clock.slot)?;
+        
+        msg!(
+            "Round {} drawing - winner decided by the hash of slot {} (settle_draw)",
+            round.round_number,
+            target_slot
+        );
+        
+        Ok(())
+    }
+
+    /// Settle draw - permissionless second phase: draws from the hash of round.draw_target_slot
+    /// If that hash already left the SlotHashes window the request expires (request_draw again)
+    pub fn settle_draw(ctx: Context<SettleDraw>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_FINALIZE)?;
+        
+        let round = &mut ctx.accounts.round;
+        require!(
+            round.status == RoundStatus::Drawing && round.draw_target_slot != 0,
+            LotteryError::NoDrawRequested
+        );
+        // Committed rounds are settled by reveal_round_secret only
+        require!(!round.has_secret_commitment(), LotteryError::RevealPending);
+        
+        let (latest_slot, _) = most_recent_slot_hash(&ctx.accounts.slot_hashes)?;
+        require!(latest_slot >= round.draw_target_slot, LotteryError::DrawTargetSlotNotReached);
+        
+        let Some((slot, slot_hash)) = slot_hash_at_or_after(&ctx.accounts.slot_hashes, round.draw_target_slot)? else {
+            // Expired - returning Ok keeps the state change so request_draw can pick a new slot
+            msg!(
+                "Round {} draw expired - slot {} left the SlotHashes window, call request_draw again",
+                round.round_number,
+                round.draw_target_slot
+            );
+            round.draw_target_slot = 0;
+            return Ok(());
+        };
+        
+        // Buyer entropy is mixed in, so neither the validator nor any single buyer controls the seed
+        let seed = draw::slot_hash_seed(&slot_hash, &round.entropy_accumulator);
+        
+        // Store winning_index and the draw proof in Round and mark as Ended
+        let winning_index = round.record_draw(seed, slot, slot_hash, [0u8; 32]);
+        
+        msg!(
+            "Round {} finalized at slot {}. Winning index: {} (out of {} total tickets)",
+            round.round_number,
+            slot,
+            winning_index,
+            round.total_tickets
+        );
+        
+        Ok(())
+    }
+
+    /// Consume randomness - permissionless settlement of a Drawing round once the oracle has fulfilled
+    /// Draw seed = oracle randomness
+    pub fn consume_randomness(ctx: Context<ConsumeRandomness>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_FINALIZE)?;
+        
+        let round = &mut ctx.accounts.round;
+        require!(round.status == RoundStatus::Drawing, LotteryError::RoundNotDrawing);
+        
+        let request_info = &ctx.accounts.oracle_request;
+        require!(
+            request_info.key() == round.oracle_request
+                && request_info.owner == &ctx.accounts.config.oracle_program,
+            LotteryError::InvalidOracleRequest
+        );
+        
+        let randomness = oracle::fulfilled_randomness(&request_info.try_borrow_data()?, &round.key())?
+            .ok_or(LotteryError::RandomnessNotFulfilled)?;
+        
+        let slot = Clock::get()?.slot;
+        let seed = draw::oracle_seed(&randomness, &round.entropy_accumulator);
+        let winning_index = round.record_draw(seed, slot, [0u8; 32], randomness);
+        
+        msg!(
+            "Round {} settled from oracle randomness. Winning index: {} (out of {} total tickets)",
+            round.round_number,
+            winning_index,
+            round.total_tickets
+        );
+        
+        Ok(())
+    }
+
+    /// Commit round secret - operator commits hash(secret) while the round is still open
+    /// The secret is mixed with the hash of a slot fixed by request_draw after end_timestamp, which did not
+    /// exist when the secret was committed; the operator can still withhold the reveal (the round is then
+    /// cancelled and refunded after the reveal window)
+    pub fn commit_round_secret(ctx: Context<CommitRoundSecret>, commitment: [u8; 32]) -> Result<()> {
+        let round = &mut ctx.accounts.round;
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(round.status == RoundStatus::Active, LotteryError::RoundNotActive);
+        require!(now < round.end_timestamp, LotteryError::RoundExpired);
+        require!(!round.has_secret_commitment(), LotteryError::SecretAlreadyCommitted);
+        require!(commitment != [0u8; 32], LotteryError::InvalidSecret);
+        
+        round.secret_commitment = commitment;
+        
+        msg!("Round {} secret committed", round.round_number);
+        
+        Ok(())
+    }
+
+    /// Reveal round secret - draws a committed round once its draw slot exists, before the reveal deadline
+    /// Permissionless: only the holder of the committed secret can produce a matching reveal
+    /// Seed = hash(secret, hash of round.draw_target_slot) - the slot is fixed by request_draw, not the revealer
+    /// The reveal must land while that slot hash is still in SlotHashes (~512 slots)
+    pub fn reveal_round_secret(ctx: Context<RevealRoundSecret>, secret: [u8; 32]) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_FINALIZE)?;
+        
+        let round = &mut ctx.accounts.round;
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(round.has_secret_commitment(), LotteryError::NoSecretCommitment);
+        require!(
+            round.status == RoundStatus::Drawing && round.draw_target_slot != 0,
+            LotteryError::NoDrawRequested
+        );
+        require!(round.total_tickets > 0, LotteryError::NoTicketsSold);
+        
+        let reveal_deadline = round.reveal_deadline(&ctx.accounts.config)?;
+        require!(now <= reveal_deadline, LotteryError::RevealDeadlinePassed);
+        require!(
+            hashv(&[secret.as_ref()]).to_bytes() == round.secret_commitment,
+            LotteryError::InvalidSecret
+        );
         
-        // Extract first 8 bytes as u64 for winning index calculation
-        let hash_bytes: [u8; 8] = seed.to_bytes()[..8].try_into().unwrap();
-        let winning_index = u64::from_le_bytes(hash_bytes) % round.total_tickets;
+        let (latest_slot, _) = most_recent_slot_hash(&ctx.accounts.slot_hashes)?;
+        require!(latest_slot >= round.draw_target_slot, LotteryError::DrawTargetSlotNotReached);
         
-        // Store winning_index in Round and mark as Ended
-        round.winning_index = Some(winning_index);
-        round.status = RoundStatus::Ended;
+        // No re-request for committed rounds: a missed slot falls back to the refund path
+        let (slot, slot_hash) = slot_hash_at_or_after(&ctx.accounts.slot_hashes, round.draw_target_slot)?
+            .ok_or(LotteryError::RevealSlotExpired)?;
+        let seed = draw::secret_seed(&secret, &slot_hash, &round.entropy_accumulator);
+        let winning_index = round.record_draw(seed, slot, slot_hash, secret);
         
         msg!(
-            "Round {} finalized. Winning index: {} (out of {} total tickets)",
+            "Round {} revealed and finalized at slot {}. Winning index: {} (out of {} total tickets)",
             round.round_number,
+            slot,
             winning_index,
             round.total_tickets
         );
@@ -425,9 +1503,64 @@ pub mod sollottery {
         Ok(())
     }
 
+    /// Start next round - permissionless round sequencer step
+    /// Opens round current + 1 once the current round is settled (Ended, Claimed or Cancelled)
+    /// A round still Active or Drawing keeps the sequencer - its draw may yet settle or cancel it
+    /// Payer funds rent for the next round and its vault
+    pub fn start_next_round(ctx: Context<StartNextRound>) -> Result<()> {
+        require!(
+            matches!(
+                ctx.accounts.current_round.status,
+                RoundStatus::Ended | RoundStatus::Claimed | RoundStatus::Cancelled
+            ),
+            LotteryError::RoundNotEnded
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        let next_round_number = ctx.accounts.state.current_round_number
+            .checked_add(1)
+            .ok_or(LotteryError::MathOverflow)?;
+        
+        let payer = ctx.accounts.payer.key();
+        ctx.accounts.next_round.open(next_round_number, now, &ctx.accounts.config, ctx.bumps.next_round, payer);
+        
+        // Initialize next vault (an already funded vault PDA is used as is)
+        if ctx.accounts.next_vault.lamports() == 0 {
+            let rent = Rent::get()?;
+            let min_rent = rent.minimum_balance(0);
+            let next_round_bytes = next_round_number.to_le_bytes();
+            let seeds = &[b"vault", next_round_bytes.as_ref(), &[ctx.bumps.next_vault]];
+            
+            anchor_lang::solana_program::program::invoke_signed(
+                &anchor_lang::solana_program::system_instruction::create_account(
+                    &ctx.accounts.payer.key(),
+                    &ctx.accounts.next_vault.key(),
+                    min_rent,
+                    0,
+                    &anchor_lang::solana_program::system_program::ID,
+                ),
+                &[
+                    ctx.accounts.payer.to_account_info(),
+                    ctx.accounts.next_vault.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[&seeds[..]],
+            )?;
+        }
+        
+        ctx.accounts.state.current_round_number = next_round_number;
+        
+        msg!("[LIFECYCLE] Round {} started", next_round_number);
+        
+        Ok(())
+    }
+
     /// Claim prize - PROOF-BASED verification using TicketPosition (NO LOOPS)
     /// O(1) complexity - range check only, no iteration
+    /// Only verifies the winner and pays out - the next round is opened by start_next_round / buy_tickets
     pub fn claim_prize(ctx: Context<ClaimPrize>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_CLAIMS)?;
+        
         let round = &mut ctx.accounts.round;
         let ticket_position = &mut ctx.accounts.ticket_position;
         
@@ -475,11 +1608,14 @@ pub mod sollottery {
         );
         
         // Get vault balance (handle zero-ticket rounds gracefully)
+        // The prize is what ticket sales put in the vault: its rent-exempt minimum was paid by whoever created it
         let vault_balance = ctx.accounts.round_vault.lamports();
+        let prize = vault_balance.saturating_sub(Rent::get()?.minimum_balance(0));
         
         // AUTONOMOUS LIFECYCLE: Only transfer if there's a prize (zero-ticket rounds may have no prize)
         // FIXED: Use system_instruction::transfer with invoke_signed for SystemAccount PDA vault
-        if vault_balance > 0 {
+        // The vault keeps its rent-exempt minimum, returned to the rent payer by close_round
+        if prize > 0 {
             // CRITICAL: Verify vault is owned by SystemProgram before transfer
             require!(
                 ctx.accounts.round_vault.owner == &anchor_lang::solana_program::system_program::ID,
@@ -496,7 +1632,7 @@ pub mod sollottery {
                 LotteryError::InvalidVaultBalance
             );
             
-            // Transfer entire vault balance to winner
+            // Transfer the prize to the winner
             // Vault is a SystemAccount PDA owned by SystemProgram, so we must use invoke_signed
             let vault_key = ctx.accounts.round_vault.key();
             let round_num_bytes = round.round_number.to_le_bytes();
@@ -507,7 +1643,7 @@ pub mod sollottery {
                 &anchor_lang::solana_program::system_instruction::transfer(
                     &vault_key,
                     &ctx.accounts.winner.key(),
-                    vault_balance,
+                    prize,
                 ),
                 &[
                     ctx.accounts.round_vault.to_account_info(),
@@ -519,7 +1655,7 @@ pub mod sollottery {
             
             msg!(
                 "Prize of {} lamports transferred to {}",
-                vault_balance,
+                prize,
                 ctx.accounts.winner.key()
             );
         } else {
@@ -532,91 +1668,748 @@ pub mod sollottery {
         // Set round status to Claimed
         round.status = RoundStatus::Claimed;
         
+        // Lifetime stats (profile is created here for winners who never had one)
+        let user_profile = &mut ctx.accounts.user_profile;
+        if user_profile.user == Pubkey::default() {
+            user_profile.version = UserProfile::VERSION;
+            user_profile.user = ctx.accounts.winner.key();
+        }
+        user_profile.wins = user_profile.wins.checked_add(1).ok_or(LotteryError::MathOverflow)?;
+        user_profile.lamports_won = user_profile.lamports_won
+            .checked_add(prize)
+            .ok_or(LotteryError::MathOverflow)?;
+        
         msg!(
             "Prize of {} lamports claimed by {} for round {}",
-            vault_balance,
+            prize,
             ctx.accounts.winner.key(),
             round.round_number
         );
         
-        // ========== ON-CHAIN LIFECYCLE: Auto-create next round ==========
-        let clock = Clock::get()?;
-        let now = clock.unix_timestamp;
-        let next_round_num = round.round_number + 1;
+        Ok(())
+    }
+
+    /// Claim refund - buyer recovers the prize-pool part of a position in a Cancelled round
+    /// O(1) complexity - one TicketPosition per call
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_CLAIMS)?;
+        
+        let round = &mut ctx.accounts.round;
+        let ticket_position = &mut ctx.accounts.ticket_position;
         
-        msg!("[LIFECYCLE] Auto-creating next round {} after claim", next_round_num);
+        require!(round.status == RoundStatus::Cancelled, LotteryError::RoundNotCancelled);
+        require!(
+            ticket_position.round == round.key(),
+            LotteryError::RoundNumberMismatch
+        );
+        require!(
+            ticket_position.buyer == ctx.accounts.buyer.key(),
+            LotteryError::InvalidWinner
+        );
+        require!(!ticket_position.claimed, LotteryError::AlreadyClaimed);
         
-        let next_round = &mut ctx.accounts.next_round;
+        let refund = round.vault_amount_lamports
+            .checked_mul(ticket_position.count as u64)
+            .ok_or(LotteryError::MathOverflow)?;
         
-        next_round.round_number = next_round_num;
-        next_round.start_timestamp = now;
-        next_round.end_timestamp = now + get_round_duration();
-        next_round.total_tickets = 0;
-        next_round.winning_index = None;
-        next_round.status = RoundStatus::Active;
-        next_round.bump = ctx.bumps.next_round;
+        require!(
+            ctx.accounts.round_vault.lamports() >= refund,
+            LotteryError::InvalidVaultBalance
+        );
         
-        // Initialize next vault
-        let rent = Rent::get()?;
-        let min_rent = rent.minimum_balance(0);
-        let next_vault_bump = ctx.bumps.next_vault;
-        let next_round_bytes = next_round_num.to_le_bytes();
-        let next_seeds = &[b"vault", next_round_bytes.as_ref(), &[next_vault_bump]];
-        let next_signers = &[&next_seeds[..]];
+        let round_num_bytes = round.round_number.to_le_bytes();
+        let vault_bump = ctx.bumps.round_vault;
         
         anchor_lang::solana_program::program::invoke_signed(
-            &anchor_lang::solana_program::system_instruction::create_account(
-                &ctx.accounts.winner.key(),
-                &ctx.accounts.next_vault.key(),
-                min_rent,
-                0,
-                &anchor_lang::solana_program::system_program::ID,
+            &anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.round_vault.key(),
+                &ctx.accounts.buyer.key(),
+                refund,
             ),
             &[
-                ctx.accounts.winner.to_account_info(),
-                ctx.accounts.next_vault.to_account_info(),
+                ctx.accounts.round_vault.to_account_info(),
+                ctx.accounts.buyer.to_account_info(),
                 ctx.accounts.system_program.to_account_info(),
             ],
-            next_signers,
+            &[&[b"vault", round_num_bytes.as_ref(), &[vault_bump]]],
         )?;
         
-        msg!("[LIFECYCLE] Next round {} auto-created and active", next_round_num);
+        ticket_position.claimed = true;
+        round.refunded_tickets = round.refunded_tickets
+            .checked_add(ticket_position.count as u64)
+            .ok_or(LotteryError::MathOverflow)?;
+        
+        msg!(
+            "Refund of {} lamports for {} tickets sent to {} (round {})",
+            refund,
+            ticket_position.count,
+            ctx.accounts.buyer.key(),
+            round.round_number
+        );
+        
+        Ok(())
+    }
 
+    /// Claim referral rewards - pays out the referral balance held in the referrer's UserProfile
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        require_not_paused(&ctx.accounts.config, PAUSE_CLAIMS)?;
+        
+        let amount = ctx.accounts.user_profile.referral_balance;
+        require!(amount > 0, LotteryError::NoReferralRewards);
+        
+        // The profile is program-owned, so lamports move directly; it must stay rent exempt
+        let profile_info = ctx.accounts.user_profile.to_account_info();
+        let min_rent = Rent::get()?.minimum_balance(profile_info.data_len());
+        let remaining = profile_info.lamports()
+            .checked_sub(amount)
+            .ok_or(LotteryError::InvalidVaultBalance)?;
+        require!(remaining >= min_rent, LotteryError::InvalidVaultBalance);
+        
+        **profile_info.try_borrow_mut_lamports()? = remaining;
+        **ctx.accounts.referrer.to_account_info().try_borrow_mut_lamports()? += amount;
+        
+        ctx.accounts.user_profile.referral_balance = 0;
+        
+        msg!(
+            "Referral rewards of {} lamports claimed by {}",
+            amount,
+            ctx.accounts.referrer.key()
+        );
+        
         Ok(())
     }
-}
 
-// ============ Account Structures ============
+    /// Close ticket position - refunds the position's rent to the buyer after settlement
+    pub fn close_ticket_position(ctx: Context<CloseTicketPosition>) -> Result<()> {
+        require_position_closable(
+            &ctx.accounts.round,
+            &ctx.accounts.ticket_position,
+            &ctx.accounts.buyer.key(),
+            ctx.program_id,
+        )?;
+        
+        msg!(
+            "TicketPosition {} closed, rent refunded to {}",
+            ctx.accounts.ticket_position.key(),
+            ctx.accounts.buyer.key()
+        );
+        
+        // Account closed (rent to buyer) by the close constraint
+        Ok(())
+    }
 
-/// Round account - stores information about a lottery round
-/// Fixed-size account with no Vec fields
-#[account]
-pub struct Round {
-    pub round_number: u64,
-    pub start_timestamp: i64,
-    pub end_timestamp: i64,
-    pub total_tickets: u64,
-    pub winning_index: Option<u64>,  // Index in total_tickets range (0 to total_tickets-1)
-    pub status: RoundStatus,
-    pub bump: u8,
-}
+    /// Close ticket positions - batch variant of close_ticket_position
+    /// remaining_accounts: [round, ticket_position (mut)] pairs; rounds may repeat or be already closed
+    pub fn close_ticket_positions<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseTicketPositions<'info>>,
+    ) -> Result<()> {
+        let pairs = ctx.remaining_accounts.chunks_exact(2);
+        require!(
+            !ctx.remaining_accounts.is_empty() && pairs.remainder().is_empty(),
+            LotteryError::InvalidTicketPosition
+        );
+        
+        let buyer_key = ctx.accounts.buyer.key();
+        for pair in pairs {
+            let position: Account<'info, TicketPosition> = Account::try_from(&pair[1])?;
+            require!(pair[1].is_writable, LotteryError::InvalidTicketPosition);
+            require_position_closable(&pair[0], &position, &buyer_key, ctx.program_id)?;
+            
+            position.close(ctx.accounts.buyer.to_account_info())?;
+        }
+        
+        msg!(
+            "{} TicketPositions closed, rent refunded to {}",
+            ctx.remaining_accounts.len() / 2,
+            buyer_key
+        );
+        
+        Ok(())
+    }
+
+    /// Close round - permissionless crank returning a settled round's rent to whoever paid it
+    /// Claimed rounds, Ended with zero tickets or Cancelled with every ticket refunded, at least two
+    /// behind the sequencer, are closed:
+    /// buy_tickets still accepts current - 1, and must never re-create a closed round
+    /// The compact result is kept in RoundHistory
+    pub fn close_round(ctx: Context<CloseRound>) -> Result<()> {
+        let round = &ctx.accounts.round;
+        
+        require!(
+            round.status == RoundStatus::Claimed
+                || (round.status == RoundStatus::Ended && round.total_tickets == 0)
+                || (round.status == RoundStatus::Cancelled && round.refunded_tickets == round.total_tickets),
+            LotteryError::RoundNotClosable
+        );
+        require!(
+            round.round_number.saturating_add(2) <= ctx.accounts.state.current_round_number,
+            LotteryError::RoundNotClosable
+        );
+        
+        // Rounds from before rent_payer was recorded refund the admin wallet
+        let expected_payer = if round.rent_payer == Pubkey::default() {
+            ctx.accounts.config.admin_wallet
+        } else {
+            round.rent_payer
+        };
+        require!(
+            ctx.accounts.rent_payer.key() == expected_payer,
+            LotteryError::InvalidRentPayer
+        );
+        
+        // Sweep whatever is left in the vault (its rent, or dust) to the rent payer
+        let vault_balance = ctx.accounts.round_vault.lamports();
+        if vault_balance > 0 {
+            let round_num_bytes = round.round_number.to_le_bytes();
+            anchor_lang::solana_program::program::invoke_signed(
+                &anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.round_vault.key(),
+                    &ctx.accounts.rent_payer.key(),
+                    vault_balance,
+                ),
+                &[
+                    ctx.accounts.round_vault.to_account_info(),
+                    ctx.accounts.rent_payer.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[&[b"vault", round_num_bytes.as_ref(), &[ctx.bumps.round_vault]]],
+            )?;
+        }
+        
+        let history = &mut ctx.accounts.history;
+        if history.version == 0 {
+            // First close_round created the history
+            history.version = RoundHistory::VERSION;
+            history.bump = ctx.bumps.history;
+        }
+        history.push(RoundResult {
+            round_number: round.round_number,
+            end_timestamp: round.end_timestamp,
+            total_tickets: round.total_tickets,
+            winning_index: round.winning_index,
+            prize_lamports: if round.status == RoundStatus::Cancelled {
+                0
+            } else {
+                round.vault_amount_lamports.saturating_mul(round.total_tickets)
+            },
+            draw_hash: round.draw_proof.hash,
+        });
+        
+        msg!(
+            "Round {} closed, rent and {} vault lamports returned to {}",
+            round.round_number,
+            vault_balance,
+            ctx.accounts.rent_payer.key()
+        );
+        
+        // Round account closed (rent to rent_payer) by the close constraint
+        Ok(())
+    }
+
+    /// Migrate round - permissionless in-place upgrade of a Round to Round::VERSION
+    /// Payer covers any rent increase from the larger layout
+    pub fn migrate_round(ctx: Context<MigrateAccount>) -> Result<()> {
+        let from_version = migrate_account::<Round>(
+            ctx.accounts,
+            ctx.program_id,
+            RoundV0::SIZE,
+            Round::SIZE,
+            Round::VERSION,
+            |data| Ok(RoundV0::deserialize(&mut &data[..])?.into()),
+        )?;
+        
+        // v12 grew the embedded draw_proof in place
+        if (4..12).contains(&from_version) {
+            Round::insert_draw_proof_inputs(&mut ctx.accounts.account.try_borrow_mut_data()?);
+        }
+        
+        msg!(
+            "Round {} migrated from layout v{} to v{}",
+            ctx.accounts.account.key(),
+            from_version,
+            Round::VERSION
+        );
+        
+        Ok(())
+    }
+
+    /// Migrate ticket position - permissionless in-place upgrade to TicketPosition::VERSION
+    pub fn migrate_ticket_position(ctx: Context<MigrateAccount>) -> Result<()> {
+        let from_version = migrate_account::<TicketPosition>(
+            ctx.accounts,
+            ctx.program_id,
+            TicketPositionV0::SIZE,
+            TicketPosition::SIZE,
+            TicketPosition::VERSION,
+            |data| Ok(TicketPositionV0::deserialize(&mut &data[..])?.into()),
+        )?;
+        
+        msg!(
+            "TicketPosition {} migrated from layout v{} to v{}",
+            ctx.accounts.account.key(),
+            from_version,
+            TicketPosition::VERSION
+        );
+        
+        Ok(())
+    }
+
+    /// Migrate user profile - permissionless in-place upgrade to UserProfile::VERSION
+    pub fn migrate_user_profile(ctx: Context<MigrateAccount>) -> Result<()> {
+        let from_version = migrate_account::<UserProfile>(
+            ctx.accounts,
+            ctx.program_id,
+            UserProfileV0::SIZE,
+            UserProfile::SIZE,
+            UserProfile::VERSION,
+            |data| Ok(UserProfileV0::deserialize(&mut &data[..])?.into()),
+        )?;
+        
+        msg!(
+            "UserProfile {} migrated from layout v{} to v{}",
+            ctx.accounts.account.key(),
+            from_version,
+            UserProfile::VERSION
+        );
+        
+        Ok(())
+    }
+}
+
+// ============ Account Structures ============
+
+/// Lottery Config - singleton holding every economic and admin parameter
+/// PDA seeds: ["config"]
+/// Layout rule: new fields are appended and must decode from zero bytes; the first change bumps VERSION
+/// and adds a migrate instruction for the account
+/// ConfigParams sits inside the layout (pending_params), so changing it shifts every later field
+#[account]
+#[derive(Default)]
+pub struct LotteryConfig {
+    pub version: u8,                    // Layout version (LotteryConfig::VERSION)
+    pub authority: Pubkey,              // Signs queue_config_change and propose_authority
+    pub pending_authority: Pubkey,      // Proposed authority awaiting accept_authority (default = none)
+    pub admin_wallet: Pubkey,           // Receives activation fees
+    pub ticket_price_lamports: u64,     // Must equal vault_amount + admin_amount
+    pub vault_amount_lamports: u64,     // Per ticket to the round vault (prize pool)
+    pub admin_amount_lamports: u64,     // Per ticket fee, split across fee_recipients
+    pub activation_fee_lamports: u64,   // One-time activate_user fee
+    pub max_tickets_per_round: u16,     // Sold-out cap, at most MAX_TICKETS_PER_ROUND
+    pub round_duration_seconds: i64,    // Length of a new round (and of a zero-ticket extension)
+    pub timelock_delay_seconds: i64,    // Minimum delay between queue and execute of a config change
+    pub fee_recipients: [FeeRecipient; MAX_FEE_RECIPIENTS],  // First fee_recipient_count entries are valid
+    pub fee_recipient_count: u8,
+    pub operator: Pubkey,               // Commits and reveals per-round draw secrets
+    pub reveal_window_seconds: i64,     // After end_timestamp; a missed reveal cancels the round
+    pub oracle_program: Pubkey,         // Randomness oracle for request_draw (default = oracle disabled)
+    pub activation_required: bool,      // buy_tickets needs an activated UserProfile (off for promotional rounds)
+    pub referral_bps: u16,              // Share of a referred buyer's ticket fee credited to the referrer
+    pub paused: u8,                     // Bitmask of PAUSE_* flags
+    pub pending_params: ConfigParams,   // Queued change (valid when pending_eta != 0)
+    pub pending_eta: i64,               // Earliest execution timestamp (0 = nothing queued)
+    pub bump: u8,
+}
+
+impl LotteryConfig {
+    /// Layout history: v1 initial layout
+    pub const VERSION: u8 = 1;
 
-impl Round {
     pub const SIZE: usize = 8 +  // discriminator
+        1 +  // version: u8
+        32 + // authority: Pubkey
+        32 + // pending_authority: Pubkey
+        32 + // admin_wallet: Pubkey
+        8 +  // ticket_price_lamports: u64
+        8 +  // vault_amount_lamports: u64
+        8 +  // admin_amount_lamports: u64
+        8 +  // activation_fee_lamports: u64
+        2 +  // max_tickets_per_round: u16
+        8 +  // round_duration_seconds: i64
+        8 +  // timelock_delay_seconds: i64
+        FeeRecipient::SIZE * MAX_FEE_RECIPIENTS + // fee_recipients
+        1 +  // fee_recipient_count: u8
+        32 + // operator: Pubkey
+        8 +  // reveal_window_seconds: i64
+        32 + // oracle_program: Pubkey
+        1 +  // activation_required: bool
+        2 +  // referral_bps: u16
+        1 +  // paused: u8
+        ConfigParams::SIZE + // pending_params: ConfigParams
+        8 +  // pending_eta: i64
+        1;   // bump: u8
+
+    /// Overwrite every tunable parameter (caller validates first)
+    fn apply(&mut self, params: &ConfigParams) {
+        self.admin_wallet = params.admin_wallet;
+        self.ticket_price_lamports = params.ticket_price_lamports;
+        self.vault_amount_lamports = params.vault_amount_lamports;
+        self.admin_amount_lamports = params.admin_amount_lamports;
+        self.activation_fee_lamports = params.activation_fee_lamports;
+        self.max_tickets_per_round = params.max_tickets_per_round;
+        self.round_duration_seconds = params.round_duration_seconds;
+        self.timelock_delay_seconds = params.timelock_delay_seconds;
+        self.fee_recipients = params.fee_recipients;
+        self.fee_recipient_count = params.fee_recipient_count;
+        self.operator = params.operator;
+        self.reveal_window_seconds = params.reveal_window_seconds;
+        self.oracle_program = params.oracle_program;
+        self.activation_required = params.activation_required;
+        self.referral_bps = params.referral_bps;
+    }
+
+    /// Stage a parameter change behind the timelock (replaces any queued change)
+    fn queue_change(&mut self, params: ConfigParams, now: i64) -> Result<()> {
+        validate_config_params(&params)?;
+        
+        let eta = now
+            .checked_add(self.timelock_delay_seconds)
+            .ok_or(LotteryError::MathOverflow)?;
+        self.pending_params = params;
+        // Keep eta non-zero so a zero delay at unix time 0 still reads as queued
+        self.pending_eta = eta.max(1);
+        
+        msg!("Config change queued, executable at {}", self.pending_eta);
+        
+        Ok(())
+    }
+
+    fn cancel_change(&mut self) -> Result<()> {
+        require!(self.pending_eta != 0, LotteryError::NoPendingConfigChange);
+        self.pending_eta = 0;
+        
+        msg!("Queued config change cancelled");
+        
+        Ok(())
+    }
+}
+
+/// Config Params - full replacement set of tunable parameters for queue_config_change
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ConfigParams {
+    pub admin_wallet: Pubkey,
+    pub ticket_price_lamports: u64,
+    pub vault_amount_lamports: u64,
+    pub admin_amount_lamports: u64,
+    pub activation_fee_lamports: u64,
+    pub max_tickets_per_round: u16,
+    pub round_duration_seconds: i64,
+    pub timelock_delay_seconds: i64,
+    pub fee_recipients: [FeeRecipient; MAX_FEE_RECIPIENTS],
+    pub fee_recipient_count: u8,
+    pub operator: Pubkey,
+    pub reveal_window_seconds: i64,
+    pub oracle_program: Pubkey,
+    pub activation_required: bool,
+    pub referral_bps: u16,
+}
+
+impl ConfigParams {
+    pub const SIZE: usize = 32 + // admin_wallet: Pubkey
+        8 +  // ticket_price_lamports: u64
+        8 +  // vault_amount_lamports: u64
+        8 +  // admin_amount_lamports: u64
+        8 +  // activation_fee_lamports: u64
+        2 +  // max_tickets_per_round: u16
+        8 +  // round_duration_seconds: i64
+        8 +  // timelock_delay_seconds: i64
+        FeeRecipient::SIZE * MAX_FEE_RECIPIENTS + // fee_recipients
+        1 +  // fee_recipient_count: u8
+        32 + // operator: Pubkey
+        8 +  // reveal_window_seconds: i64
+        32 + // oracle_program: Pubkey
+        1 +  // activation_required: bool
+        2;   // referral_bps: u16
+}
+
+/// Fee Recipient - one wallet and its share of the per-ticket fee in basis points
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct FeeRecipient {
+    pub wallet: Pubkey,
+    pub bps: u16,
+}
+
+impl FeeRecipient {
+    pub const SIZE: usize = 32 + // wallet: Pubkey
+        2;   // bps: u16
+}
+
+/// Lottery State - global round sequencer
+/// buy_tickets only accepts current_round_number; start_next_round only opens round N+1 once round N
+/// is settled (Ended, Claimed or Cancelled)
+/// PDA seeds: ["state"]
+/// Layout rule: new fields are appended and must decode from zero bytes; the first change bumps VERSION
+/// and adds a migrate instruction for the account
+#[account]
+pub struct LotteryState {
+    pub version: u8,                // Layout version (LotteryState::VERSION)
+    pub current_round_number: u64,  // The one canonical round open for sales
+    pub bump: u8,
+}
+
+impl LotteryState {
+    /// Layout history: v1 initial layout
+    pub const VERSION: u8 = 1;
+
+    pub const SIZE: usize = 8 +  // discriminator
+        1 +  // version: u8
+        8 +  // current_round_number: u64
+        1;   // bump: u8
+}
+
+/// Round History - compact results of rounds removed by close_round, so UIs keep showing them
+/// Ring buffer: the newest result overwrites the oldest once ROUND_HISTORY_LEN entries are stored
+/// PDA seeds: ["history"]
+/// Layout rule: new fields are appended and must decode from zero bytes; the first change bumps VERSION
+/// and adds a migrate instruction for the account
+#[account]
+pub struct RoundHistory {
+    pub version: u8,     // Layout version (RoundHistory::VERSION, 0 until close_round first writes it)
+    pub results: [RoundResult; ROUND_HISTORY_LEN],
+    pub next_index: u8,  // Slot the next result is written to
+    pub count: u8,       // Valid entries (<= ROUND_HISTORY_LEN)
+    pub bump: u8,
+}
+
+impl RoundHistory {
+    /// Layout history: v1 initial layout
+    pub const VERSION: u8 = 1;
+
+    pub const SIZE: usize = 8 +  // discriminator
+        1 +  // version: u8
+        RoundResult::SIZE * ROUND_HISTORY_LEN + // results
+        1 +  // next_index: u8
+        1 +  // count: u8
+        1;   // bump: u8
+
+    fn push(&mut self, result: RoundResult) {
+        self.results[self.next_index as usize] = result;
+        self.next_index = ((self.next_index as usize + 1) % ROUND_HISTORY_LEN) as u8;
+        if (self.count as usize) < ROUND_HISTORY_LEN {
+            self.count += 1;
+        }
+    }
+}
+
+/// Round Result - what a UI needs from a closed round; draw_hash re-derives winning_index
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RoundResult {
+    pub round_number: u64,
+    pub end_timestamp: i64,
+    pub total_tickets: u64,
+    pub winning_index: Option<u64>,
+    pub prize_lamports: u64,
+    pub draw_hash: [u8; 32],
+}
+
+impl RoundResult {
+    pub const SIZE: usize = 8 +  // round_number: u64
+        8 +  // end_timestamp: i64
+        8 +  // total_tickets: u64
+        9 +  // winning_index: Option<u64>
+        8 +  // prize_lamports: u64
+        32;  // draw_hash: [u8; 32]
+}
+
+/// Round account - stores information about a lottery round
+/// Fixed-size account with no Vec fields
+/// Layout rule: new fields are appended and must decode from zero bytes (see migrate_round)
+#[account]
+#[derive(Default)]
+pub struct Round {
+    pub version: u8,  // Layout version (Round::VERSION)
+    pub round_number: u64,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    pub total_tickets: u64,
+    pub winning_index: Option<u64>,  // Index in total_tickets range (0 to total_tickets-1)
+    pub status: RoundStatus,
+    pub bump: u8,
+    pub ticket_price_lamports: u64,  // Economics snapshotted from config when the round opened
+    pub vault_amount_lamports: u64,
+    pub admin_amount_lamports: u64,
+    pub secret_commitment: [u8; 32],  // hash(operator secret); all zero = no commit-reveal for this round
+    pub oracle_request: Pubkey,       // Pending oracle RandomnessRequest while Drawing
+    pub draw_proof: DrawProof,        // Draw seed inputs and output - seed and winning_index are recomputable from these
+    pub entropy_accumulator: [u8; 32], // Running hash of buyer keys + client entropy, mixed into the draw
+    pub draw_target_slot: u64,        // Slot whose hash settles a requested draw (0 = none / expired)
+    pub rent_payer: Pubkey,           // Funded the round account - refunded by close_round
+    pub fee_recipients: [FeeRecipient; MAX_FEE_RECIPIENTS],  // Fee split snapshotted from config when the round opened
+    pub fee_recipient_count: u8,      // 0 = opened before the snapshot existed (live config split applies)
+    pub referral_bps: u16,
+    pub draw_requested_at: i64,       // Oracle request time - unfulfilled after ORACLE_FULFILMENT_WINDOW_SECONDS = cancellable
+    pub refunded_tickets: u64,        // Tickets refunded by claim_refund - a Cancelled round closes once all are
+    pub max_tickets_per_round: u16,   // Sold-out cap snapshotted from config (0 = opened before the snapshot)
+    pub reveal_window_seconds: i64,   // Reveal window snapshotted from config (0 = opened before the snapshot)
+}
+
+impl Round {
+    /// Layout history: v1 version byte + economics snapshot, v2 secret_commitment, v3 oracle_request,
+    /// v4 draw_proof, v5 entropy_accumulator, v6 draw_target_slot, v7 rent_payer, v8 fee split snapshot,
+    /// v9 draw_requested_at, v10 refunded_tickets, v11 ticket cap and reveal window snapshot,
+    /// v12 draw_proof seed inputs (draw_proof grew in place - migrate_round moves the fields after it)
+    pub const VERSION: u8 = 12;
+
+    /// Byte offset of draw_proof (v4+)
+    const DRAW_PROOF_OFFSET: usize = 8 +  // discriminator
+        1 + 8 + 8 + 8 + 8 + 9 + 1 + 1 +   // version .. bump
+        8 + 8 + 8 +                        // ticket_price_lamports .. admin_amount_lamports
+        32 + 32;                           // secret_commitment, oracle_request
+
+    pub const SIZE: usize = 8 +  // discriminator
+        1 +  // version: u8
         8 +  // round_number: u64
         8 +  // start_timestamp: i64
         8 +  // end_timestamp: i64
         8 +  // total_tickets: u64
         9 +  // winning_index: Option<u64> (1 byte tag + 8 bytes value)
         1 +  // status: RoundStatus (enum as u8)
-        1;   // bump: u8
+        1 +  // bump: u8
+        8 +  // ticket_price_lamports: u64
+        8 +  // vault_amount_lamports: u64
+        8 +  // admin_amount_lamports: u64
+        32 + // secret_commitment: [u8; 32]
+        32 + // oracle_request: Pubkey
+        DrawProof::SIZE + // draw_proof: DrawProof
+        32 + // entropy_accumulator: [u8; 32]
+        8 +  // draw_target_slot: u64
+        32 + // rent_payer: Pubkey
+        FeeRecipient::SIZE * MAX_FEE_RECIPIENTS + // fee_recipients
+        1 +  // fee_recipient_count: u8
+        2 +  // referral_bps: u16
+        8 +  // draw_requested_at: i64
+        8 +  // refunded_tickets: u64
+        2 +  // max_tickets_per_round: u16
+        8;   // reveal_window_seconds: i64
+
+    /// Upgrade the data of a v4-v11 round already grown to Round::SIZE (new bytes zeroed): the fields
+    /// stored after the old draw_proof move past its new seed inputs, which are left zeroed
+    fn insert_draw_proof_inputs(data: &mut [u8]) {
+        let old_end = Self::DRAW_PROOF_OFFSET + DrawProof::V4_SIZE;
+        let new_end = Self::DRAW_PROOF_OFFSET + DrawProof::SIZE;
+        data.copy_within(old_end..Self::SIZE - (new_end - old_end), new_end);
+        data[old_end..new_end].fill(0);
+    }
+
+    /// Open a fresh Active round, snapshotting the current config economics and fee split
+    /// Later config changes never alter a round that is already running
+    /// rent_payer funded the Round (and normally its vault) and gets both back from close_round
+    fn open(&mut self, round_number: u64, now: i64, config: &LotteryConfig, bump: u8, rent_payer: Pubkey) {
+        self.version = Round::VERSION;
+        self.round_number = round_number;
+        self.start_timestamp = now;
+        self.end_timestamp = now + config.round_duration_seconds;
+        self.total_tickets = 0;
+        self.winning_index = None;
+        self.status = RoundStatus::Active;
+        self.bump = bump;
+        self.ticket_price_lamports = config.ticket_price_lamports;
+        self.vault_amount_lamports = config.vault_amount_lamports;
+        self.admin_amount_lamports = config.admin_amount_lamports;
+        self.secret_commitment = [0u8; 32];
+        self.oracle_request = Pubkey::default();
+        self.draw_proof = DrawProof::default();
+        self.entropy_accumulator = [0u8; 32];
+        self.draw_target_slot = 0;
+        self.rent_payer = rent_payer;
+        self.fee_recipients = config.fee_recipients;
+        self.fee_recipient_count = config.fee_recipient_count;
+        self.referral_bps = config.referral_bps;
+        self.draw_requested_at = 0;
+        self.refunded_tickets = 0;
+        self.max_tickets_per_round = config.max_tickets_per_round;
+        self.reveal_window_seconds = config.reveal_window_seconds;
+    }
+
+    /// Fee split for purchases: (fee recipients, recipient count, referral bps)
+    /// Rounds opened before the snapshot existed (no recipients) use the live config split
+    fn fee_split(&self, config: &LotteryConfig) -> ([FeeRecipient; MAX_FEE_RECIPIENTS], usize, u16) {
+        if self.fee_recipient_count == 0 {
+            (config.fee_recipients, config.fee_recipient_count as usize, config.referral_bps)
+        } else {
+            (self.fee_recipients, self.fee_recipient_count as usize, self.referral_bps)
+        }
+    }
+
+    /// Sold-out cap for this round - rounds opened before the snapshot existed use the live config
+    fn max_tickets(&self, config: &LotteryConfig) -> u64 {
+        if self.max_tickets_per_round == 0 {
+            config.max_tickets_per_round as u64
+        } else {
+            self.max_tickets_per_round as u64
+        }
+    }
+
+    /// Last moment reveal_round_secret is accepted - rounds opened before the snapshot existed use the live config
+    fn reveal_deadline(&self, config: &LotteryConfig) -> Result<i64> {
+        let reveal_window_seconds = if self.reveal_window_seconds == 0 {
+            config.reveal_window_seconds
+        } else {
+            self.reveal_window_seconds
+        };
+        self.end_timestamp
+            .checked_add(reveal_window_seconds)
+            .ok_or(LotteryError::MathOverflow.into())
+    }
+
+    fn has_secret_commitment(&self) -> bool {
+        self.secret_commitment != [0u8; 32]
+    }
+
+    /// Close the round for a slot hash draw: settle_draw uses the hash of current_slot + offset
+    fn request_slot_draw(&mut self, current_slot: u64) -> Result<u64> {
+        let target_slot = current_slot
+            .checked_add(DRAW_TARGET_SLOT_OFFSET)
+            .ok_or(LotteryError::MathOverflow)?;
+        self.draw_target_slot = target_slot;
+        self.status = RoundStatus::Drawing;
+        Ok(target_slot)
+    }
+
+    /// A slot hash draw whose target left the SlotHashes window (settle_draw cleared the target)
+    fn slot_draw_expired(&self) -> bool {
+        self.status == RoundStatus::Drawing
+            && self.draw_target_slot == 0
+            && self.oracle_request == Pubkey::default()
+    }
+
+    /// Close the round for an oracle draw: consume_randomness settles it from the fulfilled request
+    fn request_oracle_draw(&mut self, oracle_request: Pubkey, now: i64) {
+        self.oracle_request = oracle_request;
+        self.draw_requested_at = now;
+        self.status = RoundStatus::Drawing;
+    }
+
+    /// An oracle draw still unfulfilled ORACLE_FULFILMENT_WINDOW_SECONDS after it was requested
+    fn oracle_draw_expired(&self, now: i64) -> bool {
+        self.status == RoundStatus::Drawing
+            && self.oracle_request != Pubkey::default()
+            && now > self.draw_requested_at.saturating_add(ORACLE_FULFILMENT_WINDOW_SECONDS)
+    }
+
+    /// Draw the winner from seed (see the draw module), store the proof and mark the round Ended
+    /// slot_hash / randomness: the seed's inputs besides the entropy accumulator (zero when unused)
+    fn record_draw(&mut self, seed: [u8; 32], slot: u64, slot_hash: [u8; 32], randomness: [u8; 32]) -> u64 {
+        let (winning_index, proof) = draw::draw(self.round_number, self.total_tickets, seed, slot);
+        self.winning_index = Some(winning_index);
+        self.draw_proof = DrawProof {
+            slot_hash,
+            randomness,
+            entropy_accumulator: self.entropy_accumulator,
+            ..proof
+        };
+        self.status = RoundStatus::Ended;
+        winning_index
+    }
 }
 
 /// Ticket Position - tracks a buyer's ticket position in a round
 /// Fixed-size account with no Vec fields
-/// PDA seeds: ["ticket", round.key().as_ref(), buyer.key().as_ref(), nonce.to_le_bytes()]
+/// PDA seeds: ["ticket", buyer.key().as_ref(), user_profile.purchase_count.to_le_bytes()]
+/// (positions created before the per-buyer nonce keep their old ["ticket", round, buyer, start_index] address)
+/// Layout rule: new fields are appended and must decode from zero bytes (see migrate_ticket_position)
 #[account]
 pub struct TicketPosition {
+    pub version: u8,  // Layout version (TicketPosition::VERSION)
     pub round: Pubkey,
     pub buyer: Pubkey,
     pub start_index: u64,  // Starting index in the round's ticket sequence (0-based)
@@ -626,7 +2419,10 @@ pub struct TicketPosition {
 }
 
 impl TicketPosition {
+    pub const VERSION: u8 = 1;
+
     pub const SIZE: usize = 8 +  // discriminator
+        1 +  // version: u8
         32 + // round: Pubkey
         32 + // buyer: Pubkey
         8 +  // start_index: u64
@@ -635,72 +2431,779 @@ impl TicketPosition {
         1;   // bump: u8
 }
 
-/// User Profile - stores activation status
+/// User Profile - stores activation status, the per-buyer ticket nonce and lifetime stats
+/// Layout rule: new fields are appended and must decode from zero bytes (see migrate_user_profile)
 #[account]
 pub struct UserProfile {
+    pub version: u8,  // Layout version (UserProfile::VERSION)
     pub user: Pubkey,
     pub activated: bool,
+    pub purchase_count: u64,  // Nonce for the next TicketPosition PDA
+    pub tickets_bought: u64,  // Lifetime stats - updated by buy_tickets / claim_prize
+    pub lamports_spent: u64,
+    pub rounds_entered: u64,
+    pub wins: u64,
+    pub lamports_won: u64,
+    pub last_round: u64,      // Last round a ticket was bought in (0 = none)
+    pub activated_at: i64,    // First activation timestamp (0 = never activated)
+    pub referrer: Pubkey,     // Set once in activate_user (default = no referrer)
+    pub referral_balance: u64,  // Claimable referral lamports, held in this account
+    pub referred_users: u64,  // Referrer counters
+    pub referral_tickets: u64,
+    pub referral_earned: u64, // Lifetime referral lamports (claimed + unclaimed)
+}
+
+impl UserProfile {
+    /// Layout history: v1 version byte, v2 purchase_count, v3 lifetime stats, v4 referrals
+    pub const VERSION: u8 = 4;
+
+    pub const SIZE: usize = 8 +  // discriminator
+        1 +  // version: u8
+        32 + // user: Pubkey
+        1 +  // activated: bool
+        8 +  // purchase_count: u64
+        8 +  // tickets_bought: u64
+        8 +  // lamports_spent: u64
+        8 +  // rounds_entered: u64
+        8 +  // wins: u64
+        8 +  // lamports_won: u64
+        8 +  // last_round: u64
+        8 +  // activated_at: i64
+        32 + // referrer: Pubkey
+        8 +  // referral_balance: u64
+        8 +  // referred_users: u64
+        8 +  // referral_tickets: u64
+        8;   // referral_earned: u64
+
+    /// Lifetime stats for one purchase of ticket_count tickets costing lamports
+    fn record_purchase(&mut self, round_number: u64, ticket_count: u64, lamports: u64) -> Result<()> {
+        self.tickets_bought = self.tickets_bought.checked_add(ticket_count).ok_or(LotteryError::MathOverflow)?;
+        self.lamports_spent = self.lamports_spent.checked_add(lamports).ok_or(LotteryError::MathOverflow)?;
+        if self.last_round != round_number {
+            self.rounds_entered = self.rounds_entered.checked_add(1).ok_or(LotteryError::MathOverflow)?;
+            self.last_round = round_number;
+        }
+        Ok(())
+    }
+
+    /// Referrer counters for one referred purchase crediting share lamports
+    fn record_referral(&mut self, ticket_count: u64, share: u64) -> Result<()> {
+        self.referral_tickets = self.referral_tickets.checked_add(ticket_count).ok_or(LotteryError::MathOverflow)?;
+        self.referral_balance = self.referral_balance.checked_add(share).ok_or(LotteryError::MathOverflow)?;
+        self.referral_earned = self.referral_earned.checked_add(share).ok_or(LotteryError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+// ============ Legacy Layouts ============
+// Pre-versioning (v0) layouts, read only by the migrate_* instructions
+
+/// Round v0 - original devnet layout without version byte or economics snapshot
+#[derive(AnchorDeserialize)]
+pub struct RoundV0 {
+    pub round_number: u64,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    pub total_tickets: u64,
+    pub winning_index: Option<u64>,
+    pub status: RoundStatus,
+    pub bump: u8,
+}
+
+impl RoundV0 {
+    pub const SIZE: usize = 8 + 8 + 8 + 8 + 8 + 9 + 1 + 1;
+}
+
+impl From<RoundV0> for Round {
+    fn from(legacy: RoundV0) -> Self {
+        // v0 rounds were sold at the original compiled-in economics
+        Round {
+            version: Round::VERSION,
+            round_number: legacy.round_number,
+            start_timestamp: legacy.start_timestamp,
+            end_timestamp: legacy.end_timestamp,
+            total_tickets: legacy.total_tickets,
+            winning_index: legacy.winning_index,
+            status: legacy.status,
+            bump: legacy.bump,
+            ticket_price_lamports: TICKET_PRICE_LAMPORTS,
+            vault_amount_lamports: VAULT_AMOUNT_LAMPORTS,
+            admin_amount_lamports: ADMIN_AMOUNT_LAMPORTS,
+            secret_commitment: [0u8; 32],
+            oracle_request: Pubkey::default(),
+            draw_proof: DrawProof::default(),
+            entropy_accumulator: [0u8; 32],
+            draw_target_slot: 0,
+            rent_payer: Pubkey::default(),  // Unknown - close_round refunds config.admin_wallet
+            fee_recipients: [FeeRecipient::default(); MAX_FEE_RECIPIENTS],
+            fee_recipient_count: 0,
+            referral_bps: 0,
+            draw_requested_at: 0,
+            refunded_tickets: 0,
+            max_tickets_per_round: 0,
+            reveal_window_seconds: 0,
+        }
+    }
+}
+
+/// TicketPosition v0 - original layout without version byte
+#[derive(AnchorDeserialize)]
+pub struct TicketPositionV0 {
+    pub round: Pubkey,
+    pub buyer: Pubkey,
+    pub start_index: u64,
+    pub count: u32,
+    pub claimed: bool,
+    pub bump: u8,
+}
+
+impl TicketPositionV0 {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 4 + 1 + 1;
+}
+
+impl From<TicketPositionV0> for TicketPosition {
+    fn from(legacy: TicketPositionV0) -> Self {
+        TicketPosition {
+            version: TicketPosition::VERSION,
+            round: legacy.round,
+            buyer: legacy.buyer,
+            start_index: legacy.start_index,
+            count: legacy.count,
+            claimed: legacy.claimed,
+            bump: legacy.bump,
+        }
+    }
+}
+
+/// UserProfile v0 - original layout without version byte
+#[derive(AnchorDeserialize)]
+pub struct UserProfileV0 {
+    pub user: Pubkey,
+    pub activated: bool,
+}
+
+impl UserProfileV0 {
+    pub const SIZE: usize = 8 + 32 + 1;
+}
+
+impl From<UserProfileV0> for UserProfile {
+    fn from(legacy: UserProfileV0) -> Self {
+        UserProfile {
+            version: UserProfile::VERSION,
+            user: legacy.user,
+            activated: legacy.activated,
+            purchase_count: 0,
+            tickets_bought: 0,
+            lamports_spent: 0,
+            rounds_entered: 0,
+            wins: 0,
+            lamports_won: 0,
+            last_round: 0,
+            activated_at: 0,
+            referrer: Pubkey::default(),
+            referral_balance: 0,
+            referred_users: 0,
+            referral_tickets: 0,
+            referral_earned: 0,
+        }
+    }
+}
+
+/// Multisig - M-of-N authority set
+/// When created, its PDA becomes config.authority
+/// PDA seeds: ["multisig"]
+/// Layout rule: new fields are appended and must decode from zero bytes; the first change bumps VERSION
+/// and adds a migrate instruction for the account
+#[account]
+pub struct Multisig {
+    pub version: u8,  // Layout version (Multisig::VERSION)
+    pub signers: [Pubkey; MAX_MULTISIG_SIGNERS],  // First signer_count entries are valid
+    pub signer_count: u8,
+    pub threshold: u8,
+    pub signer_set_seqno: u32,  // Bumped on signer or threshold changes - stale proposals cannot execute
+    pub proposal_count: u64,    // Next proposal index
+    pub bump: u8,
+}
+
+impl Multisig {
+    /// Layout history: v1 initial layout
+    pub const VERSION: u8 = 1;
+
+    pub const SIZE: usize = 8 +  // discriminator
+        1 +  // version: u8
+        32 * MAX_MULTISIG_SIGNERS + // signers: [Pubkey; MAX_MULTISIG_SIGNERS]
+        1 +  // signer_count: u8
+        1 +  // threshold: u8
+        4 +  // signer_set_seqno: u32
+        8 +  // proposal_count: u64
+        1;   // bump: u8
+
+    fn signer_index(&self, key: &Pubkey) -> Option<usize> {
+        self.signers[..self.signer_count as usize]
+            .iter()
+            .position(|signer| signer == key)
+    }
+
+    /// Replace the signer set (caller validates first)
+    fn set_signers(&mut self, signers: &[Pubkey], threshold: u8) {
+        self.signers = [Pubkey::default(); MAX_MULTISIG_SIGNERS];
+        self.signers[..signers.len()].copy_from_slice(signers);
+        self.signer_count = signers.len() as u8;
+        self.threshold = threshold;
+    }
+}
+
+/// Proposal - a privileged action awaiting multisig approvals
+/// PDA seeds: ["proposal", index.to_le_bytes()]
+/// Layout rule: new fields are appended and must decode from zero bytes; the first change bumps VERSION
+/// and adds a migrate instruction for the account
+#[account]
+pub struct Proposal {
+    pub version: u8,  // Layout version (Proposal::VERSION)
+    pub index: u64,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    pub approvals: u16,         // Bitmask over multisig.signers
+    pub signer_set_seqno: u32,  // Must match multisig.signer_set_seqno to approve/execute
+    pub executed: bool,
+    pub bump: u8,
+}
+
+impl Proposal {
+    /// Layout history: v1 initial layout
+    pub const VERSION: u8 = 1;
+
+    pub const SIZE: usize = 8 +  // discriminator
+        1 +  // version: u8
+        8 +  // index: u64
+        32 + // proposer: Pubkey
+        ProposalAction::SIZE + // action: ProposalAction
+        2 +  // approvals: u16
+        4 +  // signer_set_seqno: u32
+        1 +  // executed: bool
+        1;   // bump: u8
+}
+
+/// Proposal Action - privileged operations that require the multisig threshold
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[allow(clippy::large_enum_variant)] // Serialized into a fixed-size account; Proposal is already boxed
+pub enum ProposalAction {
+    QueueConfigChange(ConfigParams),
+    CancelConfigChange,
+    ProposeAuthority { new_authority: Pubkey },
+    SetPauseFlags { flags: u8 },
+    ChangeThreshold { threshold: u8 },
+    ChangeSigners {
+        signers: [Pubkey; MAX_MULTISIG_SIGNERS],
+        signer_count: u8,
+        threshold: u8,
+    },
+    WithdrawTreasury { recipient: Pubkey, amount: u64 },
+    CancelRound { round_number: u64 },
+}
+
+impl ProposalAction {
+    const CHANGE_SIGNERS_SIZE: usize = 32 * MAX_MULTISIG_SIGNERS + 1 + 1;
+
+    pub const SIZE: usize = 1 + // enum tag
+        if ConfigParams::SIZE > Self::CHANGE_SIGNERS_SIZE {
+            ConfigParams::SIZE
+        } else {
+            Self::CHANGE_SIGNERS_SIZE
+        };
+
+    /// Early validation at proposal creation (execution re-validates against current state)
+    fn validate(&self) -> Result<()> {
+        match self {
+            ProposalAction::QueueConfigChange(params) => validate_config_params(params),
+            ProposalAction::ChangeSigners { signers, signer_count, threshold } => {
+                require!(
+                    *signer_count as usize <= MAX_MULTISIG_SIGNERS,
+                    LotteryError::InvalidMultisig
+                );
+                validate_multisig_signers(&signers[..*signer_count as usize], *threshold)
+            }
+            ProposalAction::SetPauseFlags { flags } => {
+                require!(flags & !PAUSE_ALL == 0, LotteryError::InvalidConfig);
+                Ok(())
+            }
+            ProposalAction::WithdrawTreasury { amount, .. } => {
+                require!(*amount > 0, LotteryError::InvalidProposalAccounts);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// ============ Status Enum ============
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default)]
+pub enum RoundStatus {
+    #[default]
+    Active,
+    Ended,
+    Claimed,
+    Cancelled,  // Refund path - buyers recover their prize-pool share via claim_refund
+    Drawing,    // Sales closed, waiting for settle_draw / reveal_round_secret (target slot) or consume_randomness (oracle)
+}
+
+// ============ Instruction Contexts ============
+
+#[derive(Accounts)]
+pub struct InitializeState<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ LotteryError::Unauthorized
+    )]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = LotteryState::SIZE,
+        seeds = [b"state"],
+        bump
+    )]
+    pub state: Account<'info, LotteryState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = LotteryConfig::SIZE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QueueConfigChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ LotteryError::Unauthorized
+    )]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteConfigChange<'info> {
+    /// Permissionless - the timelock is the only gate
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ LotteryError::Unauthorized
+    )]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.pending_authority == pending_authority.key() @ LotteryError::Unauthorized
+    )]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    pub pending_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPauseFlags<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ LotteryError::Unauthorized
+    )]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMultisig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ LotteryError::Unauthorized
+    )]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = Multisig::SIZE,
+        seeds = [b"multisig"],
+        bump
+    )]
+    pub multisig: Box<Account<'info, Multisig>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut, seeds = [b"multisig"], bump = multisig.bump)]
+    pub multisig: Box<Account<'info, Multisig>>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = Proposal::SIZE,
+        seeds = [b"proposal", multisig.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+    
+    /// Must be one of multisig.signers (verified in instruction)
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    #[account(seeds = [b"multisig"], bump = multisig.bump)]
+    pub multisig: Box<Account<'info, Multisig>>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+    
+    /// Must be one of multisig.signers (verified in instruction)
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    /// Multisig mode only: the multisig PDA must be the config authority
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.authority == multisig.key() @ LotteryError::Unauthorized
+    )]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    #[account(mut, seeds = [b"multisig"], bump = multisig.bump)]
+    pub multisig: Box<Account<'info, Multisig>>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+    
+    /// Must be one of multisig.signers (verified in instruction)
+    pub signer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ActivateUser<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserProfile::SIZE,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    /// CHECK: Admin wallet - verified against config.admin_wallet
+    #[account(mut)]
+    pub admin_wallet: UncheckedAccount<'info>,
+    
+    /// Optional referrer - an activated profile other than the user's own
+    #[account(mut)]
+    pub referrer_profile: Option<Box<Account<'info, UserProfile>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_number: u64)]
+pub struct InitRound<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Round::SIZE,
+        seeds = [b"round", round_number.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round: Account<'info, Round>,
+    
+    /// CHECK: Vault PDA - SystemAccount for holding SOL
+    /// Seeds: ["vault", round_number.to_le_bytes()]
+    /// Must be owned by SystemProgram (verified in instruction)
+    #[account(
+        mut,
+        seeds = [b"vault", round_number.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round_vault: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_number: u64)]
+pub struct BuyTickets<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    /// Round sequencer - round_number must be the current round
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, LotteryState>,
+    
+    /// Current active round - checked/created/ended automatically
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = Round::SIZE,
+        seeds = [b"round", round_number.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub current_round: Account<'info, Round>,
+    
+    /// Buyer profile - must be activated while config.activation_required is set
+    /// Holds the per-buyer purchase nonce for ticket PDAs (created here only with the gate off)
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserProfile::SIZE,
+        seeds = [b"user_profile", buyer.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Box<Account<'info, UserProfile>>,
+    
+    /// Referrer profile - required when user_profile.referrer is set (receives the referral share)
+    #[account(mut)]
+    pub referrer_profile: Option<Box<Account<'info, UserProfile>>>,
+    
+    /// Ticket Position PDA - one per purchase
+    /// Seeds: ["ticket", buyer.key(), user_profile.purchase_count.to_le_bytes()]
+    /// PDA is derived manually in instruction since it may be created alongside user_profile
+    /// CHECK: Verified PDA derivation in instruction
+    #[account(mut)]
+    pub ticket_position: UncheckedAccount<'info>,
+    
+    /// CHECK: Vault PDA for current round - SystemAccount PDA owned by SystemProgram
+    /// Seeds: ["vault", round_number.to_le_bytes()]
+    /// Must be owned by SystemProgram (verified in instruction)
+    #[account(
+        mut,
+        seeds = [b"vault", round_number.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub current_vault: UncheckedAccount<'info>,
+    
+    /// CHECK: Next round PDA - only used on auto-roll, created in instruction if needed
+    /// Seeds: ["round", (round_number + 1).to_le_bytes()]
+    #[account(
+        mut,
+        seeds = [b"round", (round_number + 1).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub next_round: UncheckedAccount<'info>,
+    
+    /// CHECK: Vault PDA for the next round - SystemAccount owned by SystemProgram
+    /// Seeds: ["vault", (round_number + 1).to_le_bytes()]
+    #[account(
+        mut,
+        seeds = [b"vault", (round_number + 1).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub next_vault: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestDraw<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    /// Round to close for drawing - permissionless, anyone can call
+    /// NO signer required - permissionless finalization
+    #[account(
+        mut,
+        seeds = [b"round", round.round_number.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+    
+    /// CHECK: Oracle program implementing the oracle module interface - must equal config.oracle_program
+    pub oracle_program: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: RandomnessRequest PDA created by the oracle program (oracle mode only)
+    /// Seeds (oracle program): ["request", round.key(), round.key()]
+    #[account(mut)]
+    pub oracle_request: Option<UncheckedAccount<'info>>,
+    
+    /// Pays rent for the oracle request (oracle mode only)
+    #[account(mut)]
+    pub payer: Option<Signer<'info>>,
+    
+    /// System program for account validation
+    pub system_program: Program<'info, System>,
 }
 
-impl UserProfile {
-    pub const SIZE: usize = 8 +  // discriminator
-        32 + // user: Pubkey
-        1;   // activated: bool
+#[derive(Accounts)]
+pub struct SettleDraw<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    /// Round in Drawing state with a target slot - permissionless settlement
+    #[account(
+        mut,
+        seeds = [b"round", round.round_number.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+    
+    /// CHECK: SlotHashes sysvar - address verified, read raw (too large to deserialize)
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
 }
 
-// ============ Status Enum ============
-
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum RoundStatus {
-    Active,
-    Ended,
-    Claimed,
+#[derive(Accounts)]
+pub struct ConsumeRandomness<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    /// Round in Drawing state - permissionless settlement
+    #[account(
+        mut,
+        seeds = [b"round", round.round_number.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+    
+    /// CHECK: Fulfilled RandomnessRequest - key, owner and requester verified in instruction
+    pub oracle_request: UncheckedAccount<'info>,
 }
 
-// ============ Instruction Contexts ============
-
 #[derive(Accounts)]
-pub struct ActivateUser<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+pub struct CommitRoundSecret<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = operator @ LotteryError::Unauthorized
+    )]
+    pub config: Box<Account<'info, LotteryConfig>>,
     
     #[account(
-        init_if_needed,
-        payer = user,
-        space = UserProfile::SIZE,
-        seeds = [b"user_profile", user.key().as_ref()],
-        bump
+        mut,
+        seeds = [b"round", round.round_number.to_le_bytes().as_ref()],
+        bump = round.bump
     )]
-    pub user_profile: Account<'info, UserProfile>,
+    pub round: Account<'info, Round>,
     
-    /// CHECK: Admin wallet - verified via constant
-    #[account(mut)]
-    pub admin_wallet: UncheckedAccount<'info>,
+    pub operator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevealRoundSecret<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
     
-    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [b"round", round.round_number.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+    
+    /// CHECK: SlotHashes sysvar - address verified, read raw (too large to deserialize)
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(round_number: u64)]
-pub struct InitRound<'info> {
+pub struct StartNextRound<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    #[account(mut, seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, LotteryState>,
+    
+    /// Current round - must be closed before its successor opens
+    #[account(
+        seeds = [b"round", state.current_round_number.to_le_bytes().as_ref()],
+        bump = current_round.bump
+    )]
+    pub current_round: Account<'info, Round>,
+    
     #[account(
         init,
         payer = payer,
         space = Round::SIZE,
-        seeds = [b"round", round_number.to_le_bytes().as_ref()],
+        seeds = [b"round", (state.current_round_number + 1).to_le_bytes().as_ref()],
         bump
     )]
-    pub round: Account<'info, Round>,
+    pub next_round: Account<'info, Round>,
     
-    /// CHECK: Vault PDA - SystemAccount for holding SOL
-    /// Seeds: ["vault", round_number.to_le_bytes()]
-    /// Must be owned by SystemProgram (verified in instruction)
+    /// CHECK: Vault PDA for the next round - SystemAccount owned by SystemProgram
+    /// Seeds: ["vault", (state.current_round_number + 1).to_le_bytes()]
     #[account(
         mut,
-        seeds = [b"vault", round_number.to_le_bytes().as_ref()],
+        seeds = [b"vault", (state.current_round_number + 1).to_le_bytes().as_ref()],
         bump
     )]
-    pub round_vault: UncheckedAccount<'info>,
+    pub next_vault: UncheckedAccount<'info>,
     
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -709,49 +3212,53 @@ pub struct InitRound<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(round_number: u64)]
-pub struct BuyTickets<'info> {
-    #[account(mut)]
-    pub buyer: Signer<'info>,
+pub struct ClaimPrize<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
     
-    /// Current active round - checked/created/ended automatically
     #[account(
-        init_if_needed,
-        payer = buyer,
-        space = Round::SIZE,
-        seeds = [b"round", round_number.to_le_bytes().as_ref()],
-        bump
+        mut,
+        seeds = [b"round", round.round_number.to_le_bytes().as_ref()],
+        bump = round.bump
     )]
-    pub current_round: Account<'info, Round>,
+    pub round: Account<'info, Round>,
     
-    /// Ticket Position PDA - one per purchase
-    /// Seeds: ["ticket", round.key(), buyer.key(), round.total_tickets.to_le_bytes()]
-    /// PDA is derived manually in instruction since we need to read current_round.total_tickets
-    /// CHECK: Verified PDA derivation in instruction
+    /// Ticket Position PDA - proof that user owns winning ticket
+    /// Verified using range check: start_index <= winning_index < start_index + count
     #[account(mut)]
-    pub ticket_position: UncheckedAccount<'info>,
+    pub ticket_position: Account<'info, TicketPosition>,
     
-    /// CHECK: Vault PDA for current round - SystemAccount PDA owned by SystemProgram
-    /// Seeds: ["vault", round_number.to_le_bytes()]
+    /// CHECK: Vault PDA for the round - SystemAccount owned by SystemProgram
+    /// Seeds: ["vault", round.round_number.to_le_bytes()]
     /// Must be owned by SystemProgram (verified in instruction)
     #[account(
         mut,
-        seeds = [b"vault", round_number.to_le_bytes().as_ref()],
+        seeds = [b"vault", round.round_number.to_le_bytes().as_ref()],
         bump
     )]
-    pub current_vault: UncheckedAccount<'info>,
+    pub round_vault: UncheckedAccount<'info>,
+    
+    /// Winner profile - lifetime stats (wins, lamports_won)
+    #[account(
+        init_if_needed,
+        payer = winner,
+        space = UserProfile::SIZE,
+        seeds = [b"user_profile", winner.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Box<Account<'info, UserProfile>>,
     
-    /// CHECK: Admin wallet
     #[account(mut)]
-    pub admin_wallet: UncheckedAccount<'info>,
+    pub winner: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct FinalizeRound<'info> {
-    /// Round to finalize - permissionless, anyone can call
-    /// NO signer required - permissionless finalization
+pub struct ClaimRefund<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
     #[account(
         mut,
         seeds = [b"round", round.round_number.to_le_bytes().as_ref()],
@@ -759,27 +3266,82 @@ pub struct FinalizeRound<'info> {
     )]
     pub round: Account<'info, Round>,
     
-    /// System program for account validation
+    /// Ticket Position being refunded - must belong to the round and the buyer
+    #[account(mut)]
+    pub ticket_position: Account<'info, TicketPosition>,
+    
+    /// CHECK: Vault PDA for the round - SystemAccount owned by SystemProgram
+    /// Seeds: ["vault", round.round_number.to_le_bytes()]
+    #[account(
+        mut,
+        seeds = [b"vault", round.round_number.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round_vault: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimPrize<'info> {
+pub struct ClaimReferralRewards<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    /// Referrer profile - holds the referral balance lamports
     #[account(
         mut,
-        seeds = [b"round", round.round_number.to_le_bytes().as_ref()],
-        bump = round.bump
+        seeds = [b"user_profile", referrer.key().as_ref()],
+        bump
     )]
-    pub round: Account<'info, Round>,
+    pub user_profile: Box<Account<'info, UserProfile>>,
     
-    /// Ticket Position PDA - proof that user owns winning ticket
-    /// Verified using range check: start_index <= winning_index < start_index + count
     #[account(mut)]
+    pub referrer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTicketPosition<'info> {
+    /// CHECK: Round the position belongs to - settled Round, or already removed by close_round
+    /// Verified against ticket_position.round in instruction
+    pub round: UncheckedAccount<'info>,
+    
+    /// Position to close - rent goes back to the buyer
+    #[account(mut, close = buyer)]
     pub ticket_position: Account<'info, TicketPosition>,
     
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTicketPositions<'info> {
+    /// Owner of every position in remaining_accounts - receives the rent
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRound<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, LotteryConfig>>,
+    
+    #[account(seeds = [b"state"], bump = state.bump)]
+    pub state: Account<'info, LotteryState>,
+    
+    /// Settled round to remove - rent goes back to rent_payer
+    #[account(
+        mut,
+        seeds = [b"round", round.round_number.to_le_bytes().as_ref()],
+        bump = round.bump,
+        close = rent_payer
+    )]
+    pub round: Account<'info, Round>,
+    
     /// CHECK: Vault PDA for the round - SystemAccount owned by SystemProgram
     /// Seeds: ["vault", round.round_number.to_le_bytes()]
-    /// Must be owned by SystemProgram (verified in instruction)
     #[account(
         mut,
         seeds = [b"vault", round.round_number.to_le_bytes().as_ref()],
@@ -787,28 +3349,35 @@ pub struct ClaimPrize<'info> {
     )]
     pub round_vault: UncheckedAccount<'info>,
     
-    /// Next round - auto-created after claim
+    /// CHECK: Must equal round.rent_payer (config.admin_wallet for rounds without one) - verified in instruction
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+    
     #[account(
         init_if_needed,
-        payer = winner,
-        space = Round::SIZE,
-        seeds = [b"round", (round.round_number + 1).to_le_bytes().as_ref()],
+        payer = cranker,
+        space = RoundHistory::SIZE,
+        seeds = [b"history"],
         bump
     )]
-    pub next_round: Account<'info, Round>,
+    pub history: Box<Account<'info, RoundHistory>>,
     
-    /// CHECK: Vault PDA for next round - SystemAccount owned by SystemProgram
-    /// Seeds: ["vault", (round.round_number + 1).to_le_bytes()]
-    /// Must be owned by SystemProgram (verified in instruction)
-    #[account(
-        mut,
-        seeds = [b"vault", (round.round_number + 1).to_le_bytes().as_ref()],
-        bump
-    )]
-    pub next_vault: UncheckedAccount<'info>,
+    /// Anyone - pays rent for RoundHistory the first time only
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: Program-owned account of the migrated type - owner and discriminator verified in instruction
+    #[account(mut)]
+    pub account: UncheckedAccount<'info>,
     
+    /// Pays the rent difference for the larger layout
     #[account(mut)]
-    pub winner: Signer<'info>,
+    pub payer: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
@@ -832,7 +3401,7 @@ pub enum LotteryError {
     #[msg("Maximum tickets per round reached.")]
     MaxTicketsReached,
     
-    #[msg("Round is sold out (1000 ticket cap reached).")]
+    #[msg("Round is sold out (ticket cap reached).")]
     RoundSoldOut,
     
     #[msg("No tickets were sold in this round.")]
@@ -876,4 +3445,279 @@ pub enum LotteryError {
     
     #[msg("Invalid vault balance.")]
     InvalidVaultBalance,
+    
+    #[msg("Signer is not the config authority.")]
+    Unauthorized,
+    
+    #[msg("Invalid config parameters.")]
+    InvalidConfig,
+    
+    #[msg("Invalid multisig signer set or threshold.")]
+    InvalidMultisig,
+    
+    #[msg("Signer is not a member of the multisig.")]
+    NotMultisigSigner,
+    
+    #[msg("Signer has already approved this proposal.")]
+    AlreadyApproved,
+    
+    #[msg("Proposal has not reached the multisig threshold.")]
+    NotEnoughApprovals,
+    
+    #[msg("Proposal has already been executed.")]
+    ProposalAlreadyExecuted,
+    
+    #[msg("Proposal was created under a previous multisig signer set.")]
+    ProposalStale,
+    
+    #[msg("Accounts passed for the proposal action are invalid.")]
+    InvalidProposalAccounts,
+    
+    #[msg("Treasury balance is too low for this withdrawal.")]
+    InsufficientTreasury,
+    
+    #[msg("Round is not cancelled.")]
+    RoundNotCancelled,
+    
+    #[msg("Ticket position has already been claimed.")]
+    AlreadyClaimed,
+    
+    #[msg("This instruction is paused by the lottery authority.")]
+    ProgramPaused,
+    
+    #[msg("No config change is queued.")]
+    NoPendingConfigChange,
+    
+    #[msg("The queued config change is still timelocked.")]
+    TimelockNotExpired,
+    
+    #[msg("Account cannot be migrated by this instruction.")]
+    InvalidMigrationAccount,
+    
+    #[msg("Account is already at the current layout version.")]
+    AlreadyMigrated,
+    
+    #[msg("Fee recipients must be 1-4 non-default wallets whose shares sum to 10,000 bps.")]
+    InvalidFeeSplit,
+    
+    #[msg("Fee recipient accounts do not match the config.")]
+    InvalidFeeRecipient,
+    
+    #[msg("Invalid SlotHashes sysvar account.")]
+    InvalidSlotHashes,
+    
+    #[msg("Round secret has already been committed.")]
+    SecretAlreadyCommitted,
+    
+    #[msg("Round has no secret commitment.")]
+    NoSecretCommitment,
+    
+    #[msg("Secret does not match the round commitment.")]
+    InvalidSecret,
+    
+    #[msg("Round secret can still be revealed by the operator.")]
+    RevealPending,
+    
+    #[msg("Reveal deadline has passed.")]
+    RevealDeadlinePassed,
+    
+    #[msg("Oracle program, request and payer accounts are required in oracle mode.")]
+    OracleAccountsMissing,
+    
+    #[msg("Invalid oracle program or randomness request.")]
+    InvalidOracleRequest,
+    
+    #[msg("Round is not waiting for oracle randomness.")]
+    RoundNotDrawing,
+    
+    #[msg("Oracle has not fulfilled the randomness request yet.")]
+    RandomnessNotFulfilled,
+    
+    #[msg("Round has no pending slot hash draw.")]
+    NoDrawRequested,
+    
+    #[msg("Draw target slot has not been reached yet.")]
+    DrawTargetSlotNotReached,
+    
+    #[msg("Ticket position account does not match the buyer's next purchase PDA.")]
+    InvalidTicketPosition,
+    
+    #[msg("Round is not the current round.")]
+    NotCurrentRound,
+    
+    #[msg("Invalid or missing referrer profile.")]
+    InvalidReferrer,
+    
+    #[msg("No referral rewards to claim.")]
+    NoReferralRewards,
+    
+    #[msg("Ticket position must be claimed or refunded before it can be closed.")]
+    PositionNotSettled,
+    
+    #[msg("Round cannot be closed yet.")]
+    RoundNotClosable,
+    
+    #[msg("Rent payer does not match the round.")]
+    InvalidRentPayer,
+    
+    #[msg("The reveal slot hash has left the SlotHashes window.")]
+    RevealSlotExpired,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipient(bps: u16) -> FeeRecipient {
+        FeeRecipient { wallet: Pubkey::new_unique(), bps }
+    }
+
+    /// Defaults from initialize_config with the given fee split
+    fn params(split: &[FeeRecipient]) -> ConfigParams {
+        let mut fee_recipients = [FeeRecipient::default(); MAX_FEE_RECIPIENTS];
+        fee_recipients[..split.len()].copy_from_slice(split);
+        ConfigParams {
+            admin_wallet: Pubkey::new_unique(),
+            ticket_price_lamports: TICKET_PRICE_LAMPORTS,
+            vault_amount_lamports: VAULT_AMOUNT_LAMPORTS,
+            admin_amount_lamports: ADMIN_AMOUNT_LAMPORTS,
+            activation_fee_lamports: ACTIVATION_FEE_LAMPORTS,
+            max_tickets_per_round: MAX_TICKETS_PER_ROUND,
+            round_duration_seconds: DEFAULT_ROUND_DURATION_SECONDS,
+            timelock_delay_seconds: DEFAULT_TIMELOCK_DELAY_SECONDS,
+            fee_recipients,
+            fee_recipient_count: split.len() as u8,
+            operator: Pubkey::new_unique(),
+            reveal_window_seconds: REVEAL_WINDOW_SECONDS,
+            oracle_program: Pubkey::default(),
+            activation_required: true,
+            referral_bps: 0,
+        }
+    }
+
+    #[test]
+    fn round_keeps_ticket_cap_and_reveal_window_from_open() {
+        let mut config = LotteryConfig::default();
+        config.apply(&params(&[recipient(10_000)]));
+        let mut round = Round::default();
+        round.open(7, 1_000, &config, 255, Pubkey::new_unique());
+        let deadline = 1_000 + DEFAULT_ROUND_DURATION_SECONDS + REVEAL_WINDOW_SECONDS;
+        
+        // A later config change does not reach the running round
+        config.max_tickets_per_round = 10;
+        config.reveal_window_seconds = 1;
+        assert_eq!(round.max_tickets(&config), MAX_TICKETS_PER_ROUND as u64);
+        assert_eq!(round.reveal_deadline(&config), Ok(deadline));
+        
+        // Rounds opened before the snapshot fall back to the live config
+        round.max_tickets_per_round = 0;
+        round.reveal_window_seconds = 0;
+        assert_eq!(round.max_tickets(&config), 10);
+        assert_eq!(round.reveal_deadline(&config), Ok(1_000 + DEFAULT_ROUND_DURATION_SECONDS + 1));
+    }
+
+    #[test]
+    fn v11_round_migration_keeps_fields_after_draw_proof() {
+        let mut config = LotteryConfig::default();
+        config.apply(&params(&[recipient(10_000)]));
+        let mut round = Round::default();
+        round.open(7, 1_000, &config, 255, Pubkey::new_unique());
+        round.total_tickets = 12;
+        round.entropy_accumulator = [3u8; 32];
+        round.refunded_tickets = 4;
+        round.record_draw([1u8; 32], 99, [2u8; 32], [0u8; 32]);
+        
+        let mut current = Vec::new();
+        round.try_serialize(&mut current).unwrap();
+        current.resize(Round::SIZE, 0);
+        
+        // The same round as a v11 account stores no seed inputs, grown with zeroes by migrate_account
+        let inputs = Round::DRAW_PROOF_OFFSET + DrawProof::V4_SIZE..Round::DRAW_PROOF_OFFSET + DrawProof::SIZE;
+        let mut data = current.clone();
+        data.drain(inputs.clone());
+        data.resize(Round::SIZE, 0);
+        Round::insert_draw_proof_inputs(&mut data);
+        
+        current[inputs].fill(0);
+        assert_eq!(data, current);
+        let migrated = Round::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.entropy_accumulator, round.entropy_accumulator);
+        assert_eq!(migrated.refunded_tickets, 4);
+        assert_eq!(migrated.draw_proof.seed, round.draw_proof.seed);
+    }
+
+    #[test]
+    fn split_fee_sums_exactly_with_dust_to_first_recipient() {
+        let splits = [
+            vec![recipient(10_000)],
+            vec![recipient(3_333), recipient(3_333), recipient(3_334)],
+            vec![recipient(1), recipient(9_999)],
+            vec![recipient(2_500), recipient(2_500), recipient(2_500), recipient(2_500)],
+            vec![recipient(7_001), recipient(1_333), recipient(1_666)],
+        ];
+        let totals = (0..=1_000u64).chain([ADMIN_AMOUNT_LAMPORTS * 7, u64::MAX / 3, u64::MAX]);
+        
+        for split in &splits {
+            for total in totals.clone() {
+                let shares = split_fee(total, split).unwrap();
+                let sum: u128 = shares.iter().map(|&share| share as u128).sum();
+                assert_eq!(sum, total as u128, "split {:?} of {}", shares, total);
+                
+                // Every recipient but the first gets exactly its floor share
+                for (share, recipient) in shares.iter().zip(split).skip(1) {
+                    assert_eq!(*share as u128, total as u128 * recipient.bps as u128 / BPS_DENOMINATOR as u128);
+                }
+                assert!(shares[split.len()..].iter().all(|&share| share == 0));
+            }
+        }
+    }
+
+    #[test]
+    fn validate_config_params_accepts_defaults() {
+        assert_eq!(validate_config_params(&params(&[recipient(10_000)])), Ok(()));
+        assert_eq!(
+            validate_config_params(&params(&[recipient(4_000), recipient(3_000), recipient(2_000), recipient(1_000)])),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn validate_config_params_rejects_bad_fee_splits() {
+        let invalid_fee_split = Err(LotteryError::InvalidFeeSplit.into());
+        
+        // Shares must sum to exactly 10_000 bps
+        assert_eq!(validate_config_params(&params(&[recipient(9_999)])), invalid_fee_split);
+        assert_eq!(validate_config_params(&params(&[recipient(5_000), recipient(5_001)])), invalid_fee_split);
+        // 1..=MAX_FEE_RECIPIENTS recipients, none with a zero share or default wallet
+        assert_eq!(validate_config_params(&params(&[])), invalid_fee_split);
+        assert_eq!(validate_config_params(&params(&[recipient(10_000), recipient(0)])), invalid_fee_split);
+        let mut default_wallet = params(&[recipient(10_000)]);
+        default_wallet.fee_recipients[0].wallet = Pubkey::default();
+        assert_eq!(validate_config_params(&default_wallet), invalid_fee_split);
+        let mut too_many = params(&[recipient(10_000)]);
+        too_many.fee_recipient_count = MAX_FEE_RECIPIENTS as u8 + 1;
+        assert_eq!(validate_config_params(&too_many), invalid_fee_split);
+    }
+
+    #[test]
+    fn validate_config_params_rejects_bad_economics() {
+        let invalid_config = Err(LotteryError::InvalidConfig.into());
+        let valid = params(&[recipient(10_000)]);
+        
+        let mut price_mismatch = valid.clone();
+        price_mismatch.admin_amount_lamports += 1;
+        assert_eq!(validate_config_params(&price_mismatch), invalid_config);
+        
+        let mut referral_above_fee = valid.clone();
+        referral_above_fee.referral_bps = BPS_DENOMINATOR as u16 + 1;
+        assert_eq!(validate_config_params(&referral_above_fee), invalid_config);
+        
+        let mut ticket_cap = valid.clone();
+        ticket_cap.max_tickets_per_round = MAX_TICKETS_PER_ROUND + 1;
+        assert_eq!(validate_config_params(&ticket_cap), invalid_config);
+        
+        let mut no_operator = valid;
+        no_operator.operator = Pubkey::default();
+        assert_eq!(validate_config_params(&no_operator), invalid_config);
+    }
 }
@@ -0,0 +1,177 @@
+//! Randomness oracle interface for oracle mode (config.oracle_program)
+//!
+//! Any oracle program speaking this interface can be configured; programs/mock-oracle is the local
+//! reference implementation and is not linked into this program:
+//! - request_randomness(seed: [u8; 32]) - Anchor instruction encoding, accounts
+//!   [request (mut), requester (signer), payer (mut, signer), system_program]
+//! - the request account it creates is an Anchor `RandomnessRequest` account owned by the oracle
+//!   program, read back by consume_randomness once fulfilled
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use std::convert::TryInto;
+
+use crate::LotteryError;
+
+/// Randomness request account as written by the oracle program
+#[derive(AnchorDeserialize)]
+pub struct RandomnessRequest {
+    pub requester: Pubkey,
+    pub seed: [u8; 32],
+    pub fulfilled: bool,
+    pub randomness: [u8; 32],  // Valid once fulfilled
+    pub bump: u8,
+}
+
+/// Anchor discriminator: sha256("<namespace>:<name>")[..8]
+fn discriminator(preimage: &str) -> [u8; 8] {
+    hash(preimage.as_bytes()).to_bytes()[..8].try_into().unwrap()
+}
+
+/// request_randomness instruction for oracle_program; requester must sign (invoke_signed for a PDA)
+pub fn request_randomness(
+    oracle_program: &Pubkey,
+    request: &Pubkey,
+    requester: &Pubkey,
+    payer: &Pubkey,
+    seed: [u8; 32],
+) -> Instruction {
+    let mut data = discriminator("global:request_randomness").to_vec();
+    data.extend_from_slice(&seed);
+    
+    Instruction {
+        program_id: *oracle_program,
+        accounts: vec![
+            AccountMeta::new(*request, false),
+            AccountMeta::new_readonly(*requester, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(anchor_lang::solana_program::system_program::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Randomness of a request account made for requester - None until the oracle fulfils it
+/// The caller checks the account is owned by the configured oracle program
+pub fn fulfilled_randomness(data: &[u8], requester: &Pubkey) -> Result<Option<[u8; 32]>> {
+    require!(
+        data.len() >= 8 && data[..8] == discriminator("account:RandomnessRequest"),
+        LotteryError::InvalidOracleRequest
+    );
+    
+    let request = RandomnessRequest::deserialize(&mut &data[8..])?;
+    require!(request.requester == *requester, LotteryError::InvalidOracleRequest);
+    
+    Ok(request.fulfilled.then_some(request.randomness))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Round, RoundStatus, ORACLE_FULFILMENT_WINDOW_SECONDS};
+    use anchor_lang::{InstructionData, ToAccountMetas};
+
+    /// RandomnessRequest account data as the mock oracle writes it
+    fn mock_request(requester: Pubkey, fulfilled: bool, randomness: [u8; 32]) -> Vec<u8> {
+        let mut data = Vec::new();
+        mock_oracle::RandomnessRequest {
+            requester,
+            seed: requester.to_bytes(),
+            fulfilled,
+            randomness,
+            bump: 254,
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        data
+    }
+
+    fn drawing_round(request: Pubkey, now: i64) -> Round {
+        let mut round = Round {
+            round_number: 3,
+            total_tickets: 25,
+            entropy_accumulator: [9u8; 32],
+            ..Round::default()
+        };
+        round.request_oracle_draw(request, now);
+        round
+    }
+
+    #[test]
+    fn request_instruction_matches_mock_oracle() {
+        let (request, requester, payer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let seed = requester.to_bytes();
+        
+        let ix = request_randomness(&mock_oracle::ID, &request, &requester, &payer, seed);
+        assert_eq!(ix.program_id, mock_oracle::ID);
+        assert_eq!(ix.data, mock_oracle::instruction::RequestRandomness { seed }.data());
+        assert_eq!(
+            ix.accounts,
+            mock_oracle::accounts::RequestRandomness {
+                request,
+                requester,
+                payer,
+                system_program: anchor_lang::solana_program::system_program::ID,
+            }
+            .to_account_metas(None)
+        );
+    }
+
+    /// request_draw -> oracle fulfil_randomness -> consume_randomness on the round state
+    #[test]
+    fn request_fulfil_settle_lifecycle() {
+        let round_key = Pubkey::new_unique();
+        let request = Pubkey::new_unique();
+        let mut round = drawing_round(request, 1_000);
+        assert!(round.status == RoundStatus::Drawing);
+        assert_eq!(round.oracle_request, request);
+        
+        // Pending: consume_randomness has nothing to settle yet
+        assert_eq!(fulfilled_randomness(&mock_request(round_key, false, [0u8; 32]), &round_key), Ok(None));
+        
+        // Fulfilled by the oracle authority
+        let randomness = [42u8; 32];
+        let data = mock_request(round_key, true, randomness);
+        assert_eq!(fulfilled_randomness(&data, &round_key), Ok(Some(randomness)));
+        
+        // consume_randomness mixes in the buyer entropy accumulator
+        let seed = crate::draw::oracle_seed(&randomness, &round.entropy_accumulator);
+        assert_ne!(seed, randomness);
+        let winning_index = round.record_draw(seed, 77, [0u8; 32], randomness);
+        assert!(round.status == RoundStatus::Ended);
+        assert!(winning_index < round.total_tickets);
+        assert_eq!(round.winning_index, Some(winning_index));
+        assert_eq!(round.draw_proof.seed, seed);
+        assert_eq!(round.draw_proof.randomness, randomness);
+        assert_eq!(round.draw_proof.expected_seed(), seed);
+        assert_eq!(crate::draw::winning_index(&round.draw_proof.hash, round.total_tickets), winning_index);
+    }
+
+    #[test]
+    fn rejects_foreign_requests() {
+        let round_key = Pubkey::new_unique();
+        let invalid_request = Err(crate::LotteryError::InvalidOracleRequest.into());
+        
+        // Fulfilled for another requester
+        let other = mock_request(Pubkey::new_unique(), true, [1u8; 32]);
+        assert_eq!(fulfilled_randomness(&other, &round_key), invalid_request);
+        
+        // Some other account type
+        let mut wrong_type = mock_request(round_key, true, [1u8; 32]);
+        wrong_type[0] ^= 1;
+        assert_eq!(fulfilled_randomness(&wrong_type, &round_key), invalid_request);
+    }
+
+    #[test]
+    fn unfulfilled_request_expires_after_window() {
+        let round = drawing_round(Pubkey::new_unique(), 1_000);
+        assert!(!round.oracle_draw_expired(1_000 + ORACLE_FULFILMENT_WINDOW_SECONDS));
+        assert!(round.oracle_draw_expired(1_000 + ORACLE_FULFILMENT_WINDOW_SECONDS + 1));
+        
+        // A slot hash draw never counts as an oracle timeout
+        let mut slot_round = Round::default();
+        slot_round.request_slot_draw(10).unwrap();
+        assert!(!slot_round.oracle_draw_expired(i64::MAX));
+    }
+}
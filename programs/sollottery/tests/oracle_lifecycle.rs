@@ -0,0 +1,299 @@
+//! Oracle draw lifecycle through the real instructions: buy_tickets -> request_draw (CPI into the mock
+//! oracle) -> fulfill_randomness -> consume_randomness -> claim_prize, on solana-program-test
+
+use anchor_lang::{
+    prelude::*, solana_program::entrypoint::ProgramResult, AccountSerialize, InstructionData,
+    ToAccountMetas,
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account as SolanaAccount,
+    instruction::{Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+use sollottery::{draw, FeeRecipient, LotteryConfig, LotteryError, LotteryState, Round, RoundStatus};
+
+const TICKET_PRICE: u64 = 10_000_000;
+const VAULT_AMOUNT: u64 = 9_000_000;
+const ROUND_DURATION: i64 = 60;
+
+// Anchor entrypoints tie the slice lifetime to the AccountInfo lifetime; the test runtime does not
+fn process_lottery(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    sollottery::entry(program_id, Box::leak(Box::new(accounts.to_vec())), data)
+}
+
+fn process_oracle(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    mock_oracle::entry(program_id, Box::leak(Box::new(accounts.to_vec())), data)
+}
+
+fn pda(seeds: &[&[u8]], program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(seeds, program_id).0
+}
+
+fn round_pda(round_number: u64) -> Pubkey {
+    pda(&[b"round", &round_number.to_le_bytes()], &sollottery::ID)
+}
+
+fn vault_pda(round_number: u64) -> Pubkey {
+    pda(&[b"vault", &round_number.to_le_bytes()], &sollottery::ID)
+}
+
+/// Anchor account data (discriminator + borsh) owned by the lottery program
+fn program_account<T: AccountSerialize>(account: &T, size: usize) -> SolanaAccount {
+    let mut data = Vec::with_capacity(size);
+    account.try_serialize(&mut data).unwrap();
+    data.resize(size, 0);
+    SolanaAccount {
+        lamports: 1_000_000_000,
+        data,
+        owner: sollottery::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Oracle-mode config without the activation gate, the whole fee to one recipient
+fn oracle_config(fee_wallet: Pubkey) -> LotteryConfig {
+    let mut config = LotteryConfig {
+        version: LotteryConfig::VERSION,
+        ticket_price_lamports: TICKET_PRICE,
+        vault_amount_lamports: VAULT_AMOUNT,
+        admin_amount_lamports: TICKET_PRICE - VAULT_AMOUNT,
+        max_tickets_per_round: 100,
+        round_duration_seconds: ROUND_DURATION,
+        fee_recipient_count: 1,
+        reveal_window_seconds: 3_600,
+        oracle_program: mock_oracle::ID,
+        bump: Pubkey::find_program_address(&[b"config"], &sollottery::ID).1,
+        ..LotteryConfig::default()
+    };
+    config.fee_recipients[0] = FeeRecipient { wallet: fee_wallet, bps: 10_000 };
+    config
+}
+
+async fn send(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> std::result::Result<(), BanksClientError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+async fn read_round(context: &mut ProgramTestContext, round_number: u64) -> Round {
+    let account = context.banks_client.get_account(round_pda(round_number)).await.unwrap().unwrap();
+    Round::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+fn lottery_error(error: BanksClientError) -> Option<u32> {
+    match error.unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn oracle_draw_settles_round_through_mock_oracle() {
+    let buyer = Keypair::new();
+    let fee_wallet = Pubkey::new_unique();
+
+    let mut program_test = ProgramTest::new("sollottery", sollottery::ID, processor!(process_lottery));
+    program_test.add_program("mock_oracle", mock_oracle::ID, processor!(process_oracle));
+    program_test.add_account(
+        pda(&[b"config"], &sollottery::ID),
+        program_account(&oracle_config(fee_wallet), LotteryConfig::SIZE),
+    );
+    program_test.add_account(
+        pda(&[b"state"], &sollottery::ID),
+        program_account(
+            &LotteryState {
+                version: LotteryState::VERSION,
+                current_round_number: 1,
+                bump: Pubkey::find_program_address(&[b"state"], &sollottery::ID).1,
+            },
+            LotteryState::SIZE,
+        ),
+    );
+    for wallet in [buyer.pubkey(), fee_wallet] {
+        program_test.add_account(
+            wallet,
+            SolanaAccount::new(10_000_000_000, 0, &system_program::ID),
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let oracle_authority = context.payer.pubkey();
+
+    // Oracle deployment: the test payer fulfils requests
+    let oracle_state = pda(&[b"oracle"], &mock_oracle::ID);
+    send(
+        &mut context,
+        Instruction {
+            program_id: mock_oracle::ID,
+            accounts: mock_oracle::accounts::InitializeOracle {
+                oracle: oracle_state,
+                authority: oracle_authority,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: mock_oracle::instruction::InitializeOracle {}.data(),
+        },
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Round 1 opens on the first purchase
+    let user_profile = pda(&[b"user_profile", buyer.pubkey().as_ref()], &sollottery::ID);
+    let ticket_position = pda(&[b"ticket", buyer.pubkey().as_ref(), &0u64.to_le_bytes()], &sollottery::ID);
+    let mut buy_accounts = sollottery::accounts::BuyTickets {
+        buyer: buyer.pubkey(),
+        config: pda(&[b"config"], &sollottery::ID),
+        state: pda(&[b"state"], &sollottery::ID),
+        current_round: round_pda(1),
+        user_profile,
+        referrer_profile: None,
+        ticket_position,
+        current_vault: vault_pda(1),
+        next_round: round_pda(2),
+        next_vault: vault_pda(2),
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    buy_accounts.push(AccountMeta::new(fee_wallet, false));
+    send(
+        &mut context,
+        Instruction {
+            program_id: sollottery::ID,
+            accounts: buy_accounts,
+            data: sollottery::instruction::BuyTickets {
+                round_number: 1,
+                ticket_count: 5,
+                client_entropy: [7u8; 32],
+            }
+            .data(),
+        },
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+
+    let round = read_round(&mut context, 1).await;
+    assert!(round.status == RoundStatus::Active);
+    assert_eq!(round.total_tickets, 5);
+
+    // Past end_timestamp the round can be handed to the oracle
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = round.end_timestamp + 1;
+    context.set_sysvar(&clock);
+
+    let round_key = round_pda(1);
+    let oracle_request = pda(&[b"request", round_key.as_ref(), round_key.as_ref()], &mock_oracle::ID);
+    send(
+        &mut context,
+        Instruction {
+            program_id: sollottery::ID,
+            accounts: sollottery::accounts::RequestDraw {
+                config: pda(&[b"config"], &sollottery::ID),
+                round: round_key,
+                oracle_program: Some(mock_oracle::ID),
+                oracle_request: Some(oracle_request),
+                payer: Some(oracle_authority),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: sollottery::instruction::RequestDraw {}.data(),
+        },
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let round = read_round(&mut context, 1).await;
+    assert!(round.status == RoundStatus::Drawing);
+    assert_eq!(round.oracle_request, oracle_request);
+
+    let consume = Instruction {
+        program_id: sollottery::ID,
+        accounts: sollottery::accounts::ConsumeRandomness {
+            config: pda(&[b"config"], &sollottery::ID),
+            round: round_key,
+            oracle_request,
+        }
+        .to_account_metas(None),
+        data: sollottery::instruction::ConsumeRandomness {}.data(),
+    };
+
+    // Nothing to consume until the oracle fulfils the request
+    let error = send(&mut context, consume.clone(), &[]).await.unwrap_err();
+    assert_eq!(
+        lottery_error(error),
+        Some(anchor_lang::error::ERROR_CODE_OFFSET + LotteryError::RandomnessNotFulfilled as u32)
+    );
+
+    let randomness = [42u8; 32];
+    send(
+        &mut context,
+        Instruction {
+            program_id: mock_oracle::ID,
+            accounts: mock_oracle::accounts::FulfillRandomness {
+                oracle: oracle_state,
+                request: oracle_request,
+                authority: oracle_authority,
+            }
+            .to_account_metas(None),
+            data: mock_oracle::instruction::FulfillRandomness { randomness }.data(),
+        },
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // A fresh blockhash so the retried consume is not deduplicated
+    context.get_new_latest_blockhash().await.unwrap();
+    send(&mut context, consume, &[]).await.unwrap();
+
+    let round = read_round(&mut context, 1).await;
+    let seed = draw::oracle_seed(&randomness, &round.entropy_accumulator);
+    assert!(round.status == RoundStatus::Ended);
+    assert_eq!(round.draw_proof.seed, seed);
+    assert_eq!(round.draw_proof.randomness, randomness);
+    assert_eq!(round.draw_proof.entropy_accumulator, round.entropy_accumulator);
+    assert_eq!(round.draw_proof.expected_seed(), seed);
+    assert_eq!(round.winning_index, Some(draw::winning_index(&round.draw_proof.hash, round.total_tickets)));
+
+    // The only buyer holds every ticket, so the settled round pays out to them
+    let balance_before = context.banks_client.get_balance(buyer.pubkey()).await.unwrap();
+    send(
+        &mut context,
+        Instruction {
+            program_id: sollottery::ID,
+            accounts: sollottery::accounts::ClaimPrize {
+                config: pda(&[b"config"], &sollottery::ID),
+                round: round_key,
+                ticket_position,
+                round_vault: vault_pda(1),
+                user_profile,
+                winner: buyer.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: sollottery::instruction::ClaimPrize {}.data(),
+        },
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+
+    let balance_after = context.banks_client.get_balance(buyer.pubkey()).await.unwrap();
+    assert_eq!(balance_after - balance_before, 5 * VAULT_AMOUNT);
+    assert!(read_round(&mut context, 1).await.status == RoundStatus::Claimed);
+}
@@ -1,16 +1,17 @@
 /**
  * Test script for scalable lottery flow
- * Tests: initRound -> buyTickets -> endRound -> claimPrize -> auto next round
+ * Tests: buyTickets -> requestDraw -> settleDraw -> claimPrize
+ * Expects initializeConfig / initializeState to have run (slot hash mode: no oracle, no operator)
  * Verifies: No realloc, No Vec, No loops
  */
 
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
-import { PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { PublicKey, SystemProgram, SYSVAR_SLOT_HASHES_PUBKEY } from "@solana/web3.js";
+import { randomBytes } from "crypto";
 import { Sollottery } from "../target/types/sollottery";
 
 const LOTTERY_PROGRAM_ID = new PublicKey("EuLcEdX49Neyk7jhV4FQS9MmP7qpmN5Hw2dAKv1TtmtV");
-const ADMIN_WALLET = new PublicKey("2q79WzkjgEqPoBAWeEP2ih51q6TYp8D9DYWWMeLHK6WP");
 
 async function main() {
     // Setup
@@ -34,13 +35,13 @@ async function main() {
         );
     };
     
-    const getTicketPositionPda = (round: PublicKey, buyer: PublicKey, nonce: number): [PublicKey, number] => {
+    // Nonce is the buyer's UserProfile.purchase_count before the purchase
+    const getTicketPositionPda = (buyer: PublicKey, purchaseCount: number): [PublicKey, number] => {
         const nonceBuffer = Buffer.allocUnsafe(8);
-        nonceBuffer.writeBigUInt64LE(BigInt(nonce), 0);
+        nonceBuffer.writeBigUInt64LE(BigInt(purchaseCount), 0);
         return PublicKey.findProgramAddressSync(
             [
                 Buffer.from("ticket"),
-                round.toBuffer(),
                 buyer.toBuffer(),
                 nonceBuffer,
             ],
@@ -48,6 +49,14 @@ async function main() {
         );
     };
     
+    const getUserProfilePda = (user: PublicKey): [PublicKey, number] =>
+        PublicKey.findProgramAddressSync([Buffer.from("user_profile"), user.toBuffer()], program.programId);
+    
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    const [statePda] = PublicKey.findProgramAddressSync([Buffer.from("state")], program.programId);
+    
+    const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+    
     const getVaultPda = (roundNumber: number): [PublicKey, number] => {
         const roundBuffer = Buffer.allocUnsafe(8);
         roundBuffer.writeBigUInt64LE(BigInt(roundNumber), 0);
@@ -58,48 +67,56 @@ async function main() {
     };
     
     try {
-        // Step 1: Initialize Round 1
-        console.log("\n📋 Step 1: Initialize Round 1");
-        const round1 = 1;
+        // Step 1: Current round from the sequencer
+        console.log("\n📋 Step 1: Current Round");
+        const state = await program.account.lotteryState.fetch(statePda);
+        const config = await program.account.lotteryConfig.fetch(configPda);
+        const round1 = state.currentRoundNumber.toNumber();
         const [round1Pda] = getRoundPda(round1);
         const [vault1Pda] = getVaultPda(round1);
+        const [round2Pda] = getRoundPda(round1 + 1);
+        const [vault2Pda] = getVaultPda(round1 + 1);
+        console.log("Current round:", round1);
         
-        const initTx = await program.methods
-            .initRound(new anchor.BN(round1))
-            .accounts({
-                round: round1Pda,
-                roundVault: vault1Pda,
-                payer: wallet.publicKey,
-                systemProgram: SystemProgram.programId,
-            })
-            .rpc();
-        
-        console.log("✅ Round 1 initialized:", initTx);
-        
-        // Fetch round to verify
-        const roundAccount = await program.account.round.fetch(round1Pda);
-        console.log("Round data:", {
-            roundNumber: roundAccount.roundNumber.toString(),
-            totalTickets: roundAccount.totalTickets.toString(),
-            status: roundAccount.status,
-        });
-        
-        // Step 2: Buy tickets (multiple buyers)
+        // Step 2: Buy tickets
         console.log("\n📋 Step 2: Buy Tickets");
         
         // Buyer 1
         console.log("Buyer 1 purchasing...");
-        const [ticketPos1Pda] = getTicketPositionPda(round1Pda, wallet.publicKey, 0);
+        const [userProfilePda] = getUserProfilePda(wallet.publicKey);
+        const purchaseCount = await program.account.userProfile
+            .fetch(userProfilePda)
+            .then((profile) => profile.purchaseCount.toNumber())
+            .catch(() => 0); // Profile created by the first purchase
+        const [ticketPos1Pda] = getTicketPositionPda(wallet.publicKey, purchaseCount);
+        
+        // Fee split: an open round keeps its snapshot, a new round takes the live config
+        const feeSplit = await program.account.round
+            .fetch(round1Pda)
+            .then((round) => (round.feeRecipientCount > 0 ? round : config))
+            .catch(() => config);
+        const feeRecipients = feeSplit.feeRecipients.slice(0, feeSplit.feeRecipientCount).map((recipient) => ({
+            pubkey: recipient.wallet,
+            isWritable: true,
+            isSigner: false,
+        }));
+        
         const buy1Tx = await program.methods
-            .buyTickets(new anchor.BN(round1), 1)
+            .buyTickets(new anchor.BN(round1), 1, Array.from(randomBytes(32)))
             .accounts({
                 buyer: wallet.publicKey,
+                config: configPda,
+                state: statePda,
                 currentRound: round1Pda,
+                userProfile: userProfilePda,
+                referrerProfile: null,
                 ticketPosition: ticketPos1Pda,
                 currentVault: vault1Pda,
-                adminWallet: ADMIN_WALLET,
+                nextRound: round2Pda,
+                nextVault: vault2Pda,
                 systemProgram: SystemProgram.programId,
             })
+            .remainingAccounts(feeRecipients)
             .rpc();
         
         console.log("✅ Buyer 1 purchase:", buy1Tx);
@@ -116,27 +133,62 @@ async function main() {
             claimed: ticketPos1.claimed,
         });
         
-        // Step 3: End Round
-        console.log("\n📋 Step 3: End Round");
+        // Step 3: Draw - requestDraw after end_timestamp fixes a future slot, settleDraw uses its hash
+        console.log("\n📋 Step 3: Draw");
         
-        // Wait if needed (for devnet testing, rounds last 2 minutes)
-        // For now, assume round is expired or manually set end_timestamp
+        const waitSeconds = roundAfterBuy1.endTimestamp.toNumber() - Math.floor(Date.now() / 1000);
+        if (waitSeconds > 0) {
+            console.log(`Waiting ${waitSeconds}s for the round to end...`);
+            await sleep((waitSeconds + 1) * 1000);
+        }
         
-        const endTx = await program.methods
-            .endRound()
+        const requestTx = await program.methods
+            .requestDraw()
             .accounts({
+                config: configPda,
                 round: round1Pda,
+                oracleProgram: null,
+                oracleRequest: null,
+                payer: null,
+                systemProgram: SystemProgram.programId,
             })
             .rpc();
         
-        console.log("✅ Round ended:", endTx);
+        console.log("✅ Draw requested:", requestTx);
+        
+        let settleTx: string | null = null;
+        while (settleTx === null) {
+            try {
+                settleTx = await program.methods
+                    .settleDraw()
+                    .accounts({
+                        config: configPda,
+                        round: round1Pda,
+                        slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+                    })
+                    .rpc();
+            } catch (error: any) {
+                if (!error.message?.includes("DrawTargetSlotNotReached")) {
+                    throw error;
+                }
+                await sleep(1000); // Target slot not produced yet
+            }
+        }
+        
+        console.log("✅ Round settled:", settleTx);
         
         // Fetch round to check winning index
         const roundEnded = await program.account.round.fetch(round1Pda);
-        console.log("Round after end:", {
+        console.log("Round after draw:", {
             status: roundEnded.status,
             winningIndex: roundEnded.winningIndex?.toString() || null,
             totalTickets: roundEnded.totalTickets.toString(),
+            drawProof: {
+                seed: Buffer.from(roundEnded.drawProof.seed).toString("hex"),
+                slot: roundEnded.drawProof.slot.toString(),
+                slotHash: Buffer.from(roundEnded.drawProof.slotHash).toString("hex"),
+                entropyAccumulator: Buffer.from(roundEnded.drawProof.entropyAccumulator).toString("hex"),
+            },
         });
         
         // Step 4: Claim Prize (if winner)
@@ -150,31 +202,20 @@ async function main() {
             if (winningIndex >= ticketStart && winningIndex < ticketEnd) {
                 console.log(`✅ TicketPosition 1 is winner! (${winningIndex} in range [${ticketStart}, ${ticketEnd}))`);
                 
-                const [round2Pda] = getRoundPda(2);
-                const [vault2Pda] = getVaultPda(2);
-                
                 const claimTx = await program.methods
                     .claimPrize()
                     .accounts({
+                        config: configPda,
                         round: round1Pda,
                         ticketPosition: ticketPos1Pda,
                         roundVault: vault1Pda,
-                        nextRound: round2Pda,
-                        nextVault: vault2Pda,
+                        userProfile: userProfilePda,
                         winner: wallet.publicKey,
                         systemProgram: SystemProgram.programId,
                     })
                     .rpc();
                 
                 console.log("✅ Prize claimed:", claimTx);
-                
-                // Verify round 2 was created
-                const round2 = await program.account.round.fetch(round2Pda);
-                console.log("Round 2 auto-created:", {
-                    roundNumber: round2.roundNumber.toString(),
-                    status: round2.status,
-                    totalTickets: round2.totalTickets.toString(),
-                });
             } else {
                 console.log(`❌ TicketPosition 1 is NOT winner (${winningIndex} not in range [${ticketStart}, ${ticketEnd}))`);
             }